@@ -0,0 +1,82 @@
+// Terrain shader for chunk meshes with packed vertices, see `PackedVertex` in mesher.rs
+
+#import bevy_pbr::mesh_functions::{get_world_from_local, mesh_position_local_to_clip}
+
+struct VoxelSettings {
+    ao_strength: f32,
+}
+
+#ifndef WIREFRAME
+@group(2) @binding(0) var<uniform> settings: VoxelSettings;
+@group(2) @binding(1) var block_textures: texture_2d_array<f32>;
+@group(2) @binding(2) var block_sampler: sampler;
+#endif
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) voxel: vec2<u32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) shade: f32,
+    @location(2) @interpolate(flat) layer: u32,
+}
+
+// Fixed brightness per face in the mesher's face order +X -X +Y -Y +Z -Z
+fn face_shade(normal: u32) -> f32 {
+    switch normal {
+        case 2u: { return 1.0; }
+        case 3u: { return 0.5; }
+        case 0u, 1u: { return 0.8; }
+        default: { return 0.65; }
+    }
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let a = vertex.voxel.x;
+    let b = vertex.voxel.y;
+    let position = vec3<f32>(
+        f32(a & 0x3fu),
+        f32((a >> 6u) & 0x1ffu),
+        f32((a >> 15u) & 0x3fu),
+    );
+
+    var out: VertexOutput;
+    out.clip_position = mesh_position_local_to_clip(
+        get_world_from_local(vertex.instance_index),
+        vec4<f32>(position, 1.0),
+    );
+
+#ifdef WIREFRAME
+    out.uv = vec2<f32>(0.0);
+    out.shade = 1.0;
+    out.layer = 0u;
+#else
+    let normal = (a >> 21u) & 0x7u;
+    let ao = (a >> 24u) & 0x3u;
+    let corner = vec2<f32>(f32((a >> 26u) & 0x1u), f32((a >> 27u) & 0x1u));
+    let light = (b >> 16u) & 0xfu;
+
+    out.uv = corner;
+    out.layer = b & 0xffffu;
+
+    let occlusion = 1.0 - settings.ao_strength * f32(3u - ao) / 3.0;
+    out.shade = face_shade(normal) * occlusion * f32(light) / 15.0;
+#endif
+    return out;
+}
+
+#ifndef WIREFRAME
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(block_textures, block_sampler, in.uv, in.layer);
+    // Cutout blocks leave gaps where their texture is transparent
+    if color.a < 0.5 {
+        discard;
+    }
+    return vec4<f32>(color.rgb * in.shade, color.a);
+}
+#endif
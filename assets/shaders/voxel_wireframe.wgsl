@@ -0,0 +1,8 @@
+// Flat colored debug wireframe over packed chunk meshes, paired with the vertex stage of voxel.wgsl
+
+@group(2) @binding(0) var<uniform> color: vec4<f32>;
+
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    return color;
+}
@@ -1,62 +1,158 @@
 use bevy::{
     input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
     prelude::*,
+    window::{CursorGrabMode, PrimaryWindow, WindowFocused},
 };
 
+use crate::input_map::{Action, ActionInput, AxisAction};
+use crate::player::{move_and_collide, Player};
+use crate::world_generator::block::BlockRegistry;
+use crate::world_generator::chunk::CHUNK_SIZE;
+use crate::world_generator::world_generator::VoxelWorld;
+
+/// How far [`Action::FocusPivot`] looks for a block under the cursor
+const PIVOT_PICK_DISTANCE: f32 = 512.0;
+
+/// How the camera moves, cycled with [`Action::CycleCameraMode`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Free flight that stops at loaded terrain
+    #[default]
+    Fly,
+    /// Turns around [`CameraController::orbit_focus`], movement moves the pivot
+    Orbit,
+    /// Eyes of the [`Player`] body
+    FirstPerson,
+    /// Behind the [`Player`] body on a spring arm that shortens in front of terrain
+    ThirdPerson,
+    /// Free flight through terrain
+    Spectator,
+}
+
+impl CameraMode {
+    pub const ALL: [CameraMode; 5] = [
+        CameraMode::Fly,
+        CameraMode::Orbit,
+        CameraMode::FirstPerson,
+        CameraMode::ThirdPerson,
+        CameraMode::Spectator,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Whether the camera follows the [`Player`] body instead of moving freely
+    pub fn has_body(self) -> bool {
+        matches!(self, CameraMode::FirstPerson | CameraMode::ThirdPerson)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CameraMode::Fly => "fly",
+            CameraMode::Orbit => "orbit",
+            CameraMode::FirstPerson => "first person",
+            CameraMode::ThirdPerson => "third person",
+            CameraMode::Spectator => "spectator",
+        }
+    }
+}
+
+/// Blend from the position the camera had when it switched into a mode that places it
+#[derive(Clone, Copy, Debug)]
+pub struct CameraTransition {
+    pub from: Vec3,
+    pub elapsed: f32,
+}
+
+/// What the orbit pivot snaps to on [`Action::FocusPivot`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PivotTarget {
+    /// Center of the clicked block
+    #[default]
+    Block,
+    /// Center of the chunk holding the clicked block
+    Chunk,
+}
+
 /// Provides basic movement functionality to the attached camera
 #[derive(Component, Clone)]
 pub struct CameraController {
     pub enabled: bool,
     pub initialized: bool,
+    /// Radians turned per pixel of mouse movement
     pub sensitivity: f32,
-    pub key_forward: KeyCode,
-    pub key_back: KeyCode,
-    pub key_left: KeyCode,
-    pub key_right: KeyCode,
-    pub key_up: KeyCode,
-    pub key_down: KeyCode,
-    pub key_run: KeyCode,
-    pub mouse_key_enable_mouse: MouseButton,
-    pub keyboard_key_enable_mouse: KeyCode,
+    /// Time constant in seconds over which mouse movement is applied, `0.0` applies it raw
+    pub look_smoothing: f32,
     pub walk_speed: f32,
     pub run_speed: f32,
-    pub friction: f32,
+    /// Rate per second at which the velocity approaches the target speed while moving
+    pub acceleration: f32,
+    /// Rate per second at which the velocity decays once movement input stops
+    pub deceleration: f32,
     pub pitch: f32,
     pub yaw: f32,
     pub velocity: Vec3,
+    /// Mouse movement not yet applied because of [`CameraController::look_smoothing`]
+    pub pending_look: Vec2,
+    pub mode: CameraMode,
+    /// Half the size of the collision box in [`CameraMode::Fly`]
+    pub collision_radius: f32,
     pub orbit_focus: Vec3,
-    pub orbit_mode: bool,
+    /// Distance from the camera to [`CameraController::orbit_focus`], taken from the
+    /// transform on the first update when not positive
+    pub orbit_distance: f32,
+    pub min_orbit_distance: f32,
+    pub max_orbit_distance: f32,
+    /// Fraction of the orbit distance the camera moves per pixel while panning
+    pub pan_sensitivity: f32,
+    pub pivot_target: PivotTarget,
+    /// Full spring arm length in [`CameraMode::ThirdPerson`]
+    pub third_person_distance: f32,
+    /// Current spring arm length, shortened at once when terrain gets in the way
+    pub spring_arm_length: f32,
+    /// Rate per second at which the spring arm extends back to its full length
+    pub spring_arm_speed: f32,
+    /// Distance kept between the camera and terrain behind it
+    pub spring_arm_margin: f32,
+    /// Seconds the camera takes to move into place after switching modes
+    pub transition_time: f32,
+    pub transition: Option<CameraTransition>,
+    /// Fraction of the orbit distance zoomed per scroll line
     pub scroll_wheel_speed: f32,
+    /// Pixels of touchpad scrolling counted as one scroll line
+    pub pixels_per_scroll_line: f32,
+    /// Turn rate in radians per second with the look stick fully deflected
+    pub gamepad_look_speed: f32,
+    /// Scroll lines per second with the orbit zoom stick fully deflected
+    pub gamepad_zoom_speed: f32,
     pub lock_y: bool,
+    /// Mouse look stays active without holding [`Action::Look`], flipped by [`Action::ToggleLook`]
+    pub look_toggled: bool,
 }
 
 impl CameraController {
-    pub fn print_controls(self) -> Self {
-        println!(
-            "
-===============================
-======= Camera Controls =======
-===============================
-    {:?} - Forward
-    {:?} - Backward
-    {:?} - Left
-    {:?} - Right
-    {:?} - Up
-    {:?} - Down
-    {:?} - Run
-    {:?}/{:?} - EnableMouse
-",
-            self.key_forward,
-            self.key_back,
-            self.key_left,
-            self.key_right,
-            self.key_up,
-            self.key_down,
-            self.key_run,
-            self.mouse_key_enable_mouse,
-            self.keyboard_key_enable_mouse,
-        );
-        self
+    pub fn look_active(&self, actions: &ActionInput) -> bool {
+        self.enabled && (self.look_toggled || actions.pressed(Action::Look))
+    }
+
+    /// Switches to `mode`, keeping the camera where it is unless the mode places it
+    pub fn set_mode(&mut self, mode: CameraMode, transform: &Transform) {
+        if mode == self.mode {
+            return;
+        }
+        if mode == CameraMode::Orbit {
+            // Orbit around the point in front of the camera, so switching does not move it
+            self.orbit_focus =
+                transform.translation - transform.rotation * Vec3::Z * self.orbit_distance;
+        }
+        self.transition = mode.has_body().then_some(CameraTransition {
+            from: transform.translation,
+            elapsed: 0.0,
+        });
+        self.velocity = Vec3::ZERO;
+        self.mode = mode;
     }
 }
 
@@ -65,99 +161,139 @@ impl Default for CameraController {
         Self {
             enabled: true,
             initialized: false,
-            sensitivity: 0.25,
-            key_forward: KeyCode::KeyW,
-            key_back: KeyCode::KeyS,
-            key_left: KeyCode::KeyA,
-            key_right: KeyCode::KeyD,
-            key_up: KeyCode::KeyE,
-            key_down: KeyCode::KeyQ,
-            key_run: KeyCode::ShiftLeft,
-            mouse_key_enable_mouse: MouseButton::Left,
-            keyboard_key_enable_mouse: KeyCode::KeyM,
+            sensitivity: 0.004,
+            look_smoothing: 0.0,
             walk_speed: 5.0,
             run_speed: 15.0,
-            friction: 0.5,
+            acceleration: 20.0,
+            deceleration: 40.0,
             pitch: 0.0,
             yaw: 0.0,
             velocity: Vec3::ZERO,
+            pending_look: Vec2::ZERO,
+            mode: CameraMode::Fly,
+            collision_radius: 0.25,
             orbit_focus: Vec3::ZERO,
-            orbit_mode: false,
+            orbit_distance: 0.0,
+            min_orbit_distance: 2.0,
+            max_orbit_distance: 500.0,
+            pan_sensitivity: 0.0015,
+            pivot_target: PivotTarget::Block,
+            third_person_distance: 4.0,
+            spring_arm_length: 4.0,
+            spring_arm_speed: 6.0,
+            spring_arm_margin: 0.2,
+            transition_time: 0.4,
+            transition: None,
             scroll_wheel_speed: 0.1,
+            pixels_per_scroll_line: 20.0,
+            gamepad_look_speed: 2.5,
+            gamepad_zoom_speed: 10.0,
             lock_y: false,
+            look_toggled: false,
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn camera_controller(
     time: Res<Time>,
     mut mouse_events: EventReader<MouseMotion>,
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
     mut scroll_evr: EventReader<MouseWheel>,
-    key_input: Res<ButtonInput<KeyCode>>,
-    mut move_toggled: Local<bool>,
+    actions: ActionInput,
+    world: Option<Res<VoxelWorld>>,
+    registry: Option<Res<BlockRegistry>>,
+    players: Query<(&Transform, &Player), Without<Camera>>,
     mut query: Query<(&mut Transform, &mut CameraController), With<Camera>>,
 ) {
     let dt = time.delta_seconds();
+    let world = world.as_deref();
 
     if let Ok((mut transform, mut options)) = query.get_single_mut() {
         if !options.initialized {
             let (_roll, yaw, pitch) = transform.rotation.to_euler(EulerRot::ZYX);
             options.yaw = yaw;
             options.pitch = pitch;
+            if options.orbit_distance <= 0.0 {
+                options.orbit_distance = transform.translation.distance(options.orbit_focus);
+            }
+            options.orbit_distance = options
+                .orbit_distance
+                .clamp(options.min_orbit_distance, options.max_orbit_distance);
             options.initialized = true;
         }
         if !options.enabled {
             return;
         }
 
+        if actions.just_pressed(Action::CycleCameraMode) {
+            // Modes that follow the body are skipped when there is none to follow
+            let mut mode = options.mode.next();
+            while mode.has_body() && players.is_empty() {
+                mode = mode.next();
+            }
+            options.set_mode(mode, &transform);
+        }
+        let mode = options.mode;
+
         let mut scroll_distance = 0.0;
 
-        // Handle scroll input
+        // Handle scroll input, touchpads report pixels instead of lines
         for ev in scroll_evr.read() {
-            match ev.unit {
-                MouseScrollUnit::Line => {
-                    scroll_distance = ev.y;
-                }
-                MouseScrollUnit::Pixel => (),
-            }
+            scroll_distance += match ev.unit {
+                MouseScrollUnit::Line => ev.y,
+                MouseScrollUnit::Pixel => ev.y / options.pixels_per_scroll_line.max(1.0),
+            };
         }
 
-        // Handle key input
+        // Handle key input, the body modes leave movement to the player
         let mut axis_input = Vec3::ZERO;
-        if key_input.pressed(options.key_forward) {
-            axis_input.z += 1.0;
-        }
-        if key_input.pressed(options.key_back) {
-            axis_input.z -= 1.0;
-        }
-        if key_input.pressed(options.key_right) {
-            axis_input.x += 1.0;
-        }
-        if key_input.pressed(options.key_left) {
-            axis_input.x -= 1.0;
-        }
-        if key_input.pressed(options.key_up) {
-            axis_input.y += 1.0;
+        if !mode.has_body() {
+            if actions.pressed(Action::MoveForward) {
+                axis_input.z += 1.0;
+            }
+            if actions.pressed(Action::MoveBack) {
+                axis_input.z -= 1.0;
+            }
+            if actions.pressed(Action::MoveRight) {
+                axis_input.x += 1.0;
+            }
+            if actions.pressed(Action::MoveLeft) {
+                axis_input.x -= 1.0;
+            }
+            if actions.pressed(Action::MoveUp) {
+                axis_input.y += 1.0;
+            }
+            if actions.pressed(Action::MoveDown) {
+                axis_input.y -= 1.0;
+            }
+            axis_input.x += actions.axis(AxisAction::MoveX);
+            axis_input.y += actions.axis(AxisAction::MoveY);
         }
-        if key_input.pressed(options.key_down) {
-            axis_input.y -= 1.0;
+        if actions.just_pressed(Action::ToggleLook) {
+            options.look_toggled = !options.look_toggled;
         }
-        if key_input.just_pressed(options.keyboard_key_enable_mouse) {
-            *move_toggled = !*move_toggled;
+
+        // Handle gamepad input, the forward stick zooms instead of moving while orbiting
+        if matches!(mode, CameraMode::Orbit | CameraMode::ThirdPerson) {
+            scroll_distance +=
+                actions.axis(AxisAction::OrbitZoom) * options.gamepad_zoom_speed * dt;
+        } else if !mode.has_body() {
+            axis_input.z += actions.axis(AxisAction::MoveZ);
         }
 
         // Apply movement update
         if axis_input != Vec3::ZERO {
-            let max_speed = if key_input.pressed(options.key_run) {
+            let max_speed = if actions.pressed(Action::Run) {
                 options.run_speed
             } else {
                 options.walk_speed
             };
-            options.velocity = axis_input.normalize() * max_speed;
+            // Keeps partial stick deflection slower while keyboard diagonals stay normalized
+            let target = axis_input.clamp_length_max(1.0) * max_speed;
+            options.velocity = approach(options.velocity, target, options.acceleration, dt);
         } else {
-            let friction = options.friction.clamp(0.0, 1.0);
-            options.velocity *= 1.0 - friction;
+            options.velocity = approach(options.velocity, Vec3::ZERO, options.deceleration, dt);
             if options.velocity.length_squared() < 1e-6 {
                 options.velocity = Vec3::ZERO;
             }
@@ -167,58 +303,240 @@ pub fn camera_controller(
         let mut translation_delta = options.velocity.x * dt * *right
             + options.velocity.y * dt * Vec3::Y
             + options.velocity.z * dt * *forward;
-        let mut scroll_translation = Vec3::ZERO;
-        if options.orbit_mode && options.scroll_wheel_speed > 0.0 {
-            scroll_translation = scroll_distance
-                * transform.translation.distance(options.orbit_focus)
-                * options.scroll_wheel_speed
-                * *forward;
-        }
         if options.lock_y {
             translation_delta *= Vec3::new(1.0, 0.0, 1.0);
         }
-        transform.translation += translation_delta + scroll_translation;
-        options.orbit_focus += translation_delta;
-
-        // Handle mouse input
-        let mut mouse_delta = Vec2::ZERO;
-        if mouse_button_input.pressed(options.mouse_key_enable_mouse) || *move_toggled {
-            for mouse_event in mouse_events.read() {
-                mouse_delta += mouse_event.delta;
+        match mode {
+            CameraMode::Fly => {
+                transform.translation = match (world, registry.as_deref()) {
+                    (Some(world), Some(registry)) => {
+                        let half_extents = Vec3::splat(options.collision_radius);
+                        move_and_collide(
+                            world,
+                            registry,
+                            transform.translation,
+                            half_extents,
+                            translation_delta,
+                            false,
+                        )
+                        .0
+                    }
+                    _ => transform.translation + translation_delta,
+                };
             }
-        } else {
-            mouse_events.clear();
+            CameraMode::Spectator => transform.translation += translation_delta,
+            // Orbiting moves the pivot, the camera follows when its position is derived below
+            CameraMode::Orbit => options.orbit_focus += translation_delta,
+            CameraMode::FirstPerson | CameraMode::ThirdPerson => (),
         }
 
-        if mouse_delta != Vec2::ZERO {
-            let sensitivity = if options.orbit_mode {
+        // Handle mouse input, deltas are already per frame so they are not scaled by time
+        let mouse_motion: Vec2 = mouse_events.read().map(|event| event.delta).sum();
+        if mode == CameraMode::Orbit && actions.pressed(Action::Pan) {
+            // Scaled by distance so the pivot keeps up with the cursor at any zoom level
+            let pan = (-mouse_motion.x * *right + mouse_motion.y * *transform.up())
+                * options.orbit_distance
+                * options.pan_sensitivity;
+            options.orbit_focus += pan;
+        } else if options.look_active(&actions) {
+            options.pending_look += mouse_motion;
+        }
+        let look_smoothing = options.look_smoothing;
+        let mouse_delta = smooth_look(&mut options.pending_look, look_smoothing, dt);
+
+        let stick_look = Vec2::new(
+            actions.axis(AxisAction::LookX),
+            actions.axis(AxisAction::LookY),
+        ) * options.gamepad_look_speed
+            * dt;
+
+        if mouse_delta != Vec2::ZERO || stick_look != Vec2::ZERO {
+            let sensitivity = if mode == CameraMode::Orbit {
                 options.sensitivity * 2.0
             } else {
                 options.sensitivity
             };
             let (pitch, yaw) = (
-                (options.pitch - mouse_delta.y * 0.5 * sensitivity * dt).clamp(
+                (options.pitch - mouse_delta.y * 0.5 * sensitivity + stick_look.y).clamp(
                     -0.99 * std::f32::consts::FRAC_PI_2,
                     0.99 * std::f32::consts::FRAC_PI_2,
                 ),
-                options.yaw - mouse_delta.x * sensitivity * dt,
+                options.yaw - mouse_delta.x * sensitivity - stick_look.x,
             );
 
             // Apply look update
             transform.rotation = Quat::from_euler(EulerRot::ZYX, 0.0, yaw, pitch);
             options.pitch = pitch;
             options.yaw = yaw;
+        }
 
-            if options.orbit_mode {
-                let rot_matrix = Mat3::from_quat(transform.rotation);
-                transform.translation = options.orbit_focus
-                    + rot_matrix.mul_vec3(Vec3::new(
-                        0.0,
-                        0.0,
-                        options.orbit_focus.distance(transform.translation),
-                    ));
+        // Modes other than free flight derive the position from a pivot every frame
+        let zoom = (-scroll_distance * options.scroll_wheel_speed).exp();
+        let back = transform.rotation * Vec3::Z;
+        let body_eye = players
+            .get_single()
+            .ok()
+            .map(|(body, player)| player.eye(body.translation));
+        let target = match mode {
+            CameraMode::Orbit => {
+                // Zooming scales the distance, so it slows down near the pivot and never passes it
+                options.orbit_distance = (options.orbit_distance * zoom)
+                    .clamp(options.min_orbit_distance, options.max_orbit_distance);
+                Some(options.orbit_focus + back * options.orbit_distance)
             }
+            CameraMode::FirstPerson => body_eye,
+            CameraMode::ThirdPerson => body_eye.map(|eye| {
+                options.third_person_distance = (options.third_person_distance * zoom)
+                    .clamp(options.min_orbit_distance, options.max_orbit_distance);
+                let full = options.third_person_distance;
+                let margin = options.spring_arm_margin;
+                let limit = world
+                    .and_then(|world| world.raycast(eye, back, full + margin))
+                    .map_or(full, |hit| (hit.distance - margin).max(0.0));
+                options.spring_arm_length = if limit < options.spring_arm_length {
+                    limit
+                } else {
+                    let extend = 1.0 - (-options.spring_arm_speed * dt).exp();
+                    options.spring_arm_length + (limit - options.spring_arm_length) * extend
+                };
+                eye + back * options.spring_arm_length
+            }),
+            CameraMode::Fly | CameraMode::Spectator => None,
+        };
+
+        if let Some(target) = target {
+            let duration = options.transition_time;
+            transform.translation = match &mut options.transition {
+                Some(transition) if transition.elapsed < duration => {
+                    transition.elapsed += dt;
+                    let t = (transition.elapsed / duration).min(1.0);
+                    transition.from.lerp(target, t * t * (3.0 - 2.0 * t))
+                }
+                _ => {
+                    options.transition = None;
+                    target
+                }
+            };
+        }
+    }
+}
+
+/// Moves the orbit pivot to the block under the cursor on [`Action::FocusPivot`], or to its
+/// chunk depending on [`CameraController::pivot_target`]. The camera stays where it is and
+/// turns to face the new pivot.
+pub fn focus_orbit_pivot(
+    actions: ActionInput,
+    world: Option<Res<VoxelWorld>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut query: Query<(
+        &Camera,
+        &GlobalTransform,
+        &mut Transform,
+        &mut CameraController,
+    )>,
+) {
+    if !actions.just_pressed(Action::FocusPivot) {
+        return;
+    }
+    let Some(world) = world else {
+        return;
+    };
+    let Ok((camera, camera_transform, mut transform, mut options)) = query.get_single_mut() else {
+        return;
+    };
+    if !options.enabled || options.mode != CameraMode::Orbit {
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(ray) = cursor_ray(window, camera, camera_transform) else {
+        return;
+    };
+    let Some(hit) = world.raycast(ray.origin, *ray.direction, PIVOT_PICK_DISTANCE) else {
+        return;
+    };
+
+    let focus = match options.pivot_target {
+        PivotTarget::Block => hit.block.center(),
+        PivotTarget::Chunk => {
+            hit.block.chunk().origin().as_vec3() + Vec3::splat(0.5 * CHUNK_SIZE as f32)
         }
+    };
+
+    transform.look_at(focus, Vec3::Y);
+    let (_roll, yaw, pitch) = transform.rotation.to_euler(EulerRot::ZYX);
+    options.yaw = yaw;
+    options.pitch = pitch;
+    options.orbit_focus = focus;
+    options.orbit_distance = transform
+        .translation
+        .distance(focus)
+        .clamp(options.min_orbit_distance, options.max_orbit_distance);
+}
+
+/// Ray from the camera through the cursor. A locked cursor has no position, so the ray goes
+/// through the center of the view instead.
+pub fn cursor_ray(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Ray3d> {
+    let cursor = window.cursor_position().unwrap_or(window.size() / 2.0);
+    camera.viewport_to_world(camera_transform, cursor)
+}
+
+/// Moves `current` towards `target` by exponential decay at `rate` per second,
+/// giving the same curve regardless of how the time is split into frames
+pub(crate) fn approach(current: Vec3, target: Vec3, rate: f32, dt: f32) -> Vec3 {
+    current.lerp(target, 1.0 - (-rate.max(0.0) * dt).exp())
+}
+
+/// Takes the part of the pending mouse movement that is due this frame
+fn smooth_look(pending: &mut Vec2, smoothing: f32, dt: f32) -> Vec2 {
+    let applied = if smoothing > 0.0 {
+        *pending * (1.0 - (-dt / smoothing).exp())
+    } else {
+        *pending
+    };
+    *pending -= applied;
+    if pending.length_squared() < 1e-6 {
+        *pending = Vec2::ZERO;
+    }
+    applied
+}
+
+/// Locks and hides the cursor on the primary window while mouse look is active.
+/// Escape or losing focus turns the look toggle off and gives the cursor back.
+pub fn grab_cursor(
+    actions: ActionInput,
+    mut focus_events: EventReader<WindowFocused>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut query: Query<&mut CameraController, With<Camera>>,
+) {
+    let Ok(mut options) = query.get_single_mut() else {
+        return;
+    };
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+
+    let focus_lost = focus_events.read().any(|event| !event.focused);
+    if focus_lost || actions.just_pressed(Action::ReleaseCursor) {
+        options.look_toggled = false;
+    }
+
+    let grabbed = window.focused && options.look_active(&actions);
+    let grab_mode = if grabbed {
+        CursorGrabMode::Locked
+    } else {
+        CursorGrabMode::None
+    };
+    // Only touch the window on changes, every mutation is forwarded to the OS window
+    if window.cursor.grab_mode != grab_mode || window.cursor.visible == grabbed {
+        window.cursor.grab_mode = grab_mode;
+        window.cursor.visible = !grabbed;
     }
 }
 
@@ -229,6 +547,62 @@ pub struct CameraControllerPlugin;
 
 impl Plugin for CameraControllerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, camera_controller);
+        app.add_systems(
+            Update,
+            (focus_orbit_pivot, camera_controller, grab_cursor).chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulate<T>(fps: f32, seconds: f32, mut step: impl FnMut(f32) -> T) -> T {
+        let dt = 1.0 / fps;
+        let frames = (seconds * fps).round() as usize;
+        let mut last = step(dt);
+        for _ in 1..frames {
+            last = step(dt);
+        }
+        last
+    }
+
+    #[test]
+    fn damping_is_frame_rate_independent() {
+        let decelerate = |fps| {
+            let mut velocity = Vec3::new(10.0, 0.0, 0.0);
+            simulate(fps, 0.1, |dt| {
+                velocity = approach(velocity, Vec3::ZERO, 5.0, dt);
+                velocity
+            })
+        };
+        let (slow, fast) = (decelerate(30.0), decelerate(240.0));
+        assert!(slow.abs_diff_eq(fast, 1e-4), "{slow} != {fast}");
+        assert!((slow.x - 10.0 * (-0.5f32).exp()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn smoothed_look_applies_the_same_rotation_at_any_frame_rate() {
+        let look = |fps| {
+            let mut pending = Vec2::new(100.0, -40.0);
+            let mut applied = Vec2::ZERO;
+            simulate(fps, 0.1, |dt| {
+                applied += smooth_look(&mut pending, 0.05, dt);
+                applied
+            })
+        };
+        let (slow, fast) = (look(30.0), look(240.0));
+        assert!(slow.abs_diff_eq(fast, 1e-3), "{slow} != {fast}");
+    }
+
+    #[test]
+    fn raw_look_applies_everything_at_once() {
+        let mut pending = Vec2::new(12.0, 3.0);
+        assert_eq!(
+            smooth_look(&mut pending, 0.0, 1.0 / 60.0),
+            Vec2::new(12.0, 3.0)
+        );
+        assert_eq!(pending, Vec2::ZERO);
     }
 }
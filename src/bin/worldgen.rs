@@ -0,0 +1,292 @@
+//! Headless world generation, runs under `MinimalPlugins` without a window or GPU.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use bevy::prelude::*;
+
+use bevy_minecraft_clone::world_generator::block::BlockRegistry;
+use bevy_minecraft_clone::world_generator::chunk::{
+    BlockId, CHUNK_VOLUME, MAX_CHUNK_Y, MIN_CHUNK_Y,
+};
+use bevy_minecraft_clone::world_generator::config::{GenerationConfig, TerrainPreset};
+use bevy_minecraft_clone::world_generator::export::{export_terrain, EXPORT_DIR};
+use bevy_minecraft_clone::world_generator::maps::{write_maps, write_noise, MapArea};
+use bevy_minecraft_clone::world_generator::noise_graph::{NoiseGraph, NoiseNode};
+use bevy_minecraft_clone::world_generator::region::{write_region, RegionStats};
+use bevy_minecraft_clone::world_generator::resource_pack::{load_blocks, BLOCKS_DIR};
+use bevy_minecraft_clone::world_generator::terrain::GeneratorKind;
+use bevy_minecraft_clone::world_generator::world_generator::{
+    run_until_generated, TerrainSampler, VoxelWorld, WorldGeneratorPlugin, WorldSettings,
+};
+
+const USAGE: &str = "\
+Usage: worldgen <command> [options]
+
+Commands:
+    generate    Generate a region of chunks and write it to disk
+    stats       Generate a region of chunks and print statistics
+    export      Generate a region of chunks and export its meshes to OBJ and glTF
+    maps        Render height, biome and surface maps of an area to PNG images
+    noise       Render a noise graph over an area to a grayscale PNG image
+
+Options:
+    --seed <n>      World seed
+    --radius <n>    Chunks to generate around the origin
+    --vertical-radius <n>
+                    Chunks to generate above and below y 0 (default: the whole world height)
+    --config <path> World generation config (default: assets/worldgen/default.ron)
+    --preset <name> Use a preset config: alpine, mesa, archipelago, flat or amplified
+    --generator <kind>
+                    noise (default), debug, void, superflat for grass on dirt and stone,
+                    or a superflat layer list from the bottom up: superflat=4*stone,3*dirt,grass
+                    naming blocks in assets/blocks
+    --graph <path>  Noise graph rendered by `noise` (default: the height graph of the config)
+    --area <min_x>,<min_z>,<width>,<depth>
+                    Block area rendered by `maps` and `noise` (default: -256,-256,512,512)
+    --scale <n>     Blocks per pixel for `maps` and `noise` (default: 1)
+    --out <path>    Output file for `generate` (default: world.region)
+                    and `noise` (default: exports/noise.png),
+                    or file stem for `export` (default: exports/terrain)
+                    and `maps` (default: exports/map)";
+
+enum Command {
+    Chunks(ChunkCommand),
+    Maps,
+    Noise,
+}
+
+/// Commands that generate a region of chunks first
+enum ChunkCommand {
+    Generate,
+    Stats,
+    Export,
+}
+
+struct Options {
+    command: Command,
+    settings: WorldSettings,
+    /// Blocks of the resource pack, generator block names resolve against it
+    registry: BlockRegistry,
+    area: MapArea,
+    graph: Option<NoiseNode>,
+    out: Option<PathBuf>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let command = match args.next().map(String::as_str) {
+            Some("generate") => Command::Chunks(ChunkCommand::Generate),
+            Some("stats") => Command::Chunks(ChunkCommand::Stats),
+            Some("export") => Command::Chunks(ChunkCommand::Export),
+            Some("maps") => Command::Maps,
+            Some("noise") => Command::Noise,
+            Some(other) => return Err(format!("unknown command `{other}`")),
+            None => return Err("missing command".to_string()),
+        };
+
+        let blocks = Path::new("assets").join(BLOCKS_DIR);
+        let mut options = Options {
+            command,
+            settings: WorldSettings {
+                // Unlike the game there is no loader to follow, so cover every chunk layer
+                vertical_radius: MIN_CHUNK_Y.abs().max(MAX_CHUNK_Y),
+                ..default()
+            },
+            registry: load_blocks(&blocks)
+                .map_err(|error| format!("could not load `{}`: {error}", blocks.display()))?,
+            area: MapArea::default(),
+            graph: None,
+            out: None,
+        };
+
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for `{flag}`"))?;
+            match flag.as_str() {
+                "--seed" => options.settings.seed = parse_number(flag, value)?,
+                "--radius" => options.settings.radius = parse_number(flag, value)?,
+                "--vertical-radius" => {
+                    options.settings.vertical_radius = parse_number(flag, value)?;
+                }
+                "--config" => {
+                    options.settings.generation = GenerationConfig::load(value)
+                        .map_err(|error| format!("could not load `{value}`: {error}"))?;
+                }
+                "--area" => {
+                    let numbers = value
+                        .split(',')
+                        .map(|number| parse_number(flag, number))
+                        .collect::<Result<Vec<i32>, _>>()?;
+                    let [min_x, min_z, width, depth] = numbers[..] else {
+                        return Err(format!("`{flag}` expects four comma separated numbers"));
+                    };
+                    if width <= 0 || depth <= 0 {
+                        return Err(format!("`{flag}` needs a positive width and depth"));
+                    }
+                    options.area.min = IVec2::new(min_x, min_z);
+                    options.area.size = IVec2::new(width, depth).as_uvec2();
+                }
+                "--preset" => {
+                    let preset: TerrainPreset = value.parse()?;
+                    let path = Path::new("assets").join(preset.path());
+                    options.settings.generation = GenerationConfig::load(&path)
+                        .map_err(|error| format!("could not load `{}`: {error}", path.display()))?;
+                }
+                "--generator" => {
+                    options.settings.generator = GeneratorKind::parse(value, &options.registry)?;
+                }
+                "--graph" => {
+                    options.graph = Some(
+                        NoiseNode::load(value)
+                            .map_err(|error| format!("could not load `{value}`: {error}"))?,
+                    );
+                }
+                "--scale" => options.area.scale = parse_number(flag, value)?,
+                "--out" => options.out = Some(PathBuf::from(value)),
+                _ => return Err(format!("unknown option `{flag}`")),
+            }
+        }
+
+        // Checked once every flag is read, the pixel size depends on both area and scale
+        options
+            .area
+            .validate()
+            .map_err(|error| format!("invalid `--area` or `--scale`: {error}"))?;
+        Ok(options)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for `{flag}`"))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Images sample the generator directly, no chunks need to exist
+    let command = match options.command {
+        Command::Maps => return render_maps(options),
+        Command::Noise => return render_noise(options),
+        Command::Chunks(command) => command,
+    };
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, WorldGeneratorPlugin))
+        .insert_resource(options.settings.clone())
+        .insert_resource(options.registry.clone());
+    app.finish();
+    app.cleanup();
+
+    let start = Instant::now();
+    run_until_generated(&mut app);
+    let elapsed = start.elapsed();
+
+    let world = app.world().resource::<VoxelWorld>();
+    match command {
+        ChunkCommand::Generate => {
+            let out = options.out.unwrap_or_else(|| PathBuf::from("world.region"));
+            if let Err(error) = write_region(&out, world) {
+                eprintln!("error: failed to write {}: {error}", out.display());
+                return ExitCode::FAILURE;
+            }
+            println!(
+                "Wrote {} chunks to {} in {:.2?}",
+                world.chunks.len(),
+                out.display(),
+                elapsed
+            );
+        }
+        ChunkCommand::Export => {
+            let stem = options
+                .out
+                .unwrap_or_else(|| Path::new(EXPORT_DIR).join("terrain"));
+            let registry = app.world().resource::<BlockRegistry>();
+            match export_terrain(&stem, world, registry, Path::new("assets")) {
+                Ok(export) => {
+                    if let Some(error) = &export.texture_error {
+                        eprintln!("warning: exported without a texture: {error}");
+                    }
+                    println!("Exported terrain to {}", export.files.join(", "));
+                }
+                Err(error) => {
+                    eprintln!("error: failed to export {}: {error}", stem.display());
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        ChunkCommand::Stats => {
+            let stats = RegionStats::collect(world);
+            println!("Seed:            {}", options.settings.seed);
+            println!("Chunks:          {}", stats.chunks);
+            println!("Content hash:    {:016x}", world.content_hash());
+            println!("Generation time: {elapsed:.2?}");
+            println!("Solid blocks:    {}", stats.solid_blocks);
+            println!(
+                "Block memory:    {} bytes ({} dense)",
+                stats.memory_usage,
+                stats.chunks * CHUNK_VOLUME * size_of::<BlockId>()
+            );
+            println!(
+                "Surface height:  {}..={}",
+                stats.min_surface_height, stats.max_surface_height
+            );
+            for (block, count) in stats.block_counts.iter().enumerate() {
+                println!("  block {block:>3}: {count}");
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn render_maps(options: Options) -> ExitCode {
+    let stem = options
+        .out
+        .unwrap_or_else(|| Path::new(EXPORT_DIR).join("map"));
+    let sampler = TerrainSampler::new(&options.settings);
+    match write_maps(&stem, &sampler, &options.registry, options.area) {
+        Ok(files) => {
+            for file in files {
+                println!("Wrote {}", file.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: failed to write maps: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn render_noise(options: Options) -> ExitCode {
+    let out = options
+        .out
+        .unwrap_or_else(|| Path::new(EXPORT_DIR).join("noise.png"));
+    let node = options
+        .graph
+        .unwrap_or_else(|| options.settings.generation.height.clone());
+    let graph = NoiseGraph::new(&node, options.settings.seed);
+    match write_noise(&out, &graph, options.area) {
+        Ok(()) => {
+            println!("Wrote {}", out.display());
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: failed to write {}: {error}", out.display());
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::bevy_basic_camera::{camera_controller, CameraController, CameraMode};
+use crate::input_map::{Action, ActionInput};
+use crate::world_generator::export::EXPORT_DIR;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraKeyframe {
+    /// Seconds since the start of the recording
+    pub time: f32,
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+/// Camera transform over time, stored as RON. Positions are interpolated with a Catmull-Rom
+/// spline through the keyframes and rotations with slerp, so sampling only depends on the time.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+}
+
+impl CameraPath {
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Appends a keyframe, ignoring it unless it comes after the last one
+    pub fn push(&mut self, time: f32, transform: &Transform) {
+        if self.keyframes.last().is_some_and(|last| last.time >= time) {
+            return;
+        }
+        self.keyframes.push(CameraKeyframe {
+            time,
+            translation: transform.translation,
+            rotation: transform.rotation,
+        });
+    }
+
+    /// Transform at `time`, clamped to the ends of the path
+    pub fn sample(&self, time: f32) -> Option<Transform> {
+        let keys = &self.keyframes;
+        let last = keys.len().checked_sub(1)?;
+        if time >= keys[last].time {
+            return Some(keys[last].transform());
+        }
+        let Some(i) = keys.partition_point(|key| key.time <= time).checked_sub(1) else {
+            return Some(keys[0].transform());
+        };
+
+        let (k1, k2) = (&keys[i], &keys[i + 1]);
+        let length = k2.time - k1.time;
+        let t = ((time - k1.time) / length).clamp(0.0, 1.0);
+
+        // Tangents from the neighbouring keyframes, scaled to the segment length so uneven
+        // keyframe spacing does not overshoot
+        let tangent = |before: &CameraKeyframe, after: &CameraKeyframe| {
+            (after.translation - before.translation) / (after.time - before.time) * length
+        };
+        let m1 = tangent(&keys[i.saturating_sub(1)], k2);
+        let m2 = tangent(k1, &keys[(i + 2).min(last)]);
+
+        let (t2, t3) = (t * t, t * t * t);
+        let translation = (2.0 * t3 - 3.0 * t2 + 1.0) * k1.translation
+            + (t3 - 2.0 * t2 + t) * m1
+            + (-2.0 * t3 + 3.0 * t2) * k2.translation
+            + (t3 - t2) * m2;
+
+        Some(Transform {
+            translation,
+            rotation: k1.rotation.slerp(k2.rotation, t),
+            ..default()
+        })
+    }
+
+    /// Reads a path from RON. [`CameraPath::sample`] relies on the keyframes being in time
+    /// order, so hand-edited files are sorted and only the first keyframe at each time is kept.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut path: Self = ron::from_str(&text)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if path
+            .keyframes
+            .iter()
+            .any(|keyframe| !keyframe.time.is_finite())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "camera path keyframe time is not finite",
+            ));
+        }
+        path.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        path.keyframes
+            .dedup_by(|later, earlier| later.time == earlier.time);
+        Ok(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        std::fs::write(path, text)
+    }
+}
+
+impl CameraKeyframe {
+    fn transform(&self) -> Transform {
+        Transform::from_translation(self.translation).with_rotation(self.rotation)
+    }
+}
+
+/// What [`CameraPathPlugin`] is doing with the camera
+#[derive(Resource, Clone, Debug, Default)]
+pub enum CameraPathState {
+    #[default]
+    Idle,
+    Recording {
+        path: CameraPath,
+        elapsed: f32,
+    },
+    Playing {
+        path: CameraPath,
+        time: f32,
+    },
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct CameraPathSettings {
+    pub file: PathBuf,
+    /// Seconds between recorded keyframes
+    pub keyframe_interval: f32,
+    /// Path seconds played per second
+    pub playback_speed: f32,
+    /// Advances playback by this many seconds every frame instead of the frame time, so every
+    /// run renders the same camera positions regardless of frame rate
+    pub fixed_step: Option<f32>,
+}
+
+impl Default for CameraPathSettings {
+    fn default() -> Self {
+        Self {
+            file: Path::new(EXPORT_DIR).join("camera_path.ron"),
+            keyframe_interval: 0.1,
+            playback_speed: 1.0,
+            fixed_step: None,
+        }
+    }
+}
+
+/// Records the camera to [`CameraPathSettings::file`] on [`Action::RecordCameraPath`] and
+/// plays it back on [`Action::PlayCameraPath`]. The [`CameraController`] is disabled while
+/// playing and resumes in fly mode where the path ends.
+#[derive(Default)]
+pub struct CameraPathPlugin {
+    pub settings: CameraPathSettings,
+}
+
+impl Plugin for CameraPathPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone())
+            .init_resource::<CameraPathState>()
+            .add_systems(
+                Update,
+                (toggle_camera_path, record_camera_path, play_camera_path)
+                    .chain()
+                    .after(camera_controller),
+            );
+    }
+}
+
+fn toggle_camera_path(
+    actions: ActionInput,
+    settings: Res<CameraPathSettings>,
+    mut state: ResMut<CameraPathState>,
+    mut cameras: Query<(&Transform, &mut CameraController)>,
+) {
+    let record = actions.just_pressed(Action::RecordCameraPath);
+    let play = actions.just_pressed(Action::PlayCameraPath);
+    if !record && !play {
+        return;
+    }
+
+    match std::mem::take(&mut *state) {
+        CameraPathState::Recording { mut path, elapsed } => {
+            // Keep the final position even if it falls between keyframe intervals
+            if let Ok((transform, _)) = cameras.get_single() {
+                path.push(elapsed, transform);
+            }
+            match path.save(&settings.file) {
+                Ok(()) => info!(
+                    "Saved camera path with {} keyframes to {}",
+                    path.keyframes.len(),
+                    settings.file.display()
+                ),
+                Err(error) => error!("Failed to save camera path: {error}"),
+            }
+        }
+        CameraPathState::Playing { .. } => {
+            if let Ok((transform, mut camera)) = cameras.get_single_mut() {
+                stop_playback(transform, &mut camera);
+            }
+        }
+        CameraPathState::Idle if record => {
+            info!("Recording camera path");
+            *state = CameraPathState::Recording {
+                path: CameraPath::default(),
+                elapsed: 0.0,
+            };
+        }
+        CameraPathState::Idle => match CameraPath::load(&settings.file) {
+            Ok(path) if !path.keyframes.is_empty() => {
+                if let Ok((_, mut camera)) = cameras.get_single_mut() {
+                    camera.enabled = false;
+                }
+                *state = CameraPathState::Playing { path, time: 0.0 };
+            }
+            Ok(_) => warn!("Camera path {} is empty", settings.file.display()),
+            Err(error) => error!(
+                "Failed to load camera path {}: {error}",
+                settings.file.display()
+            ),
+        },
+    }
+}
+
+fn record_camera_path(
+    time: Res<Time>,
+    settings: Res<CameraPathSettings>,
+    mut state: ResMut<CameraPathState>,
+    cameras: Query<&Transform, With<CameraController>>,
+) {
+    let CameraPathState::Recording { path, elapsed } = &mut *state else {
+        return;
+    };
+    let Ok(transform) = cameras.get_single() else {
+        return;
+    };
+
+    let due = path
+        .keyframes
+        .last()
+        .is_none_or(|last| *elapsed - last.time >= settings.keyframe_interval);
+    if due {
+        path.push(*elapsed, transform);
+    }
+    *elapsed += time.delta_seconds();
+}
+
+fn play_camera_path(
+    time: Res<Time>,
+    settings: Res<CameraPathSettings>,
+    mut state: ResMut<CameraPathState>,
+    mut cameras: Query<(&mut Transform, &mut CameraController)>,
+) {
+    let CameraPathState::Playing {
+        path,
+        time: position,
+    } = &mut *state
+    else {
+        return;
+    };
+    let Ok((mut transform, mut camera)) = cameras.get_single_mut() else {
+        return;
+    };
+
+    if let Some(sample) = path.sample(*position) {
+        *transform = sample;
+    }
+    if *position >= path.duration() {
+        stop_playback(&transform, &mut camera);
+        *state = CameraPathState::Idle;
+        return;
+    }
+    let step = settings.fixed_step.unwrap_or(time.delta_seconds());
+    *position += step * settings.playback_speed;
+}
+
+/// Hands the camera back to the controller, which takes its look angles from the transform
+fn stop_playback(transform: &Transform, camera: &mut CameraController) {
+    camera.set_mode(CameraMode::Fly, transform);
+    camera.enabled = true;
+    camera.initialized = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_map::InputMap;
+
+    fn path() -> CameraPath {
+        let mut path = CameraPath::default();
+        for (time, x) in [(0.0, 0.0), (1.0, 2.0), (1.5, 3.0), (3.0, 10.0)] {
+            let transform = Transform::from_xyz(x, 1.0, -x).looking_at(Vec3::ZERO, Vec3::Y);
+            path.push(time, &transform);
+        }
+        path
+    }
+
+    #[test]
+    fn spline_passes_through_keyframes_and_clamps_at_the_ends() {
+        let path = path();
+        for key in &path.keyframes {
+            let sample = path.sample(key.time).unwrap();
+            assert!(sample.translation.abs_diff_eq(key.translation, 1e-5));
+        }
+        assert_eq!(path.sample(-1.0), path.sample(0.0));
+        assert_eq!(path.sample(99.0), path.sample(3.0));
+        assert!(CameraPath::default().sample(0.0).is_none());
+    }
+
+    /// Plays `path` for `updates` frames with a fixed step and returns the camera transform
+    fn play(path: &CameraPath, step: f32, updates: usize) -> (Transform, App) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::input::InputPlugin))
+            .init_resource::<InputMap>()
+            .add_plugins(CameraPathPlugin {
+                settings: CameraPathSettings {
+                    fixed_step: Some(step),
+                    ..default()
+                },
+            })
+            .insert_resource(CameraPathState::Playing {
+                path: path.clone(),
+                time: 0.0,
+            });
+        let camera = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                CameraController {
+                    enabled: false,
+                    ..default()
+                },
+            ))
+            .id();
+        for _ in 0..updates {
+            app.update();
+        }
+        let transform = *app.world().get::<Transform>(camera).unwrap();
+        (transform, app)
+    }
+
+    #[test]
+    fn playback_is_independent_of_step_size() {
+        let path = path();
+        // Each update applies the current time before advancing it, so after n updates the
+        // camera shows the path at (n - 1) steps
+        let expected = path.sample(2.4).unwrap();
+        for (step, updates) in [(0.1, 25), (0.05, 49), (0.4, 7)] {
+            let (transform, app) = play(&path, step, updates);
+            assert!(
+                transform
+                    .translation
+                    .abs_diff_eq(expected.translation, 1e-3),
+                "step {step}: {} != {}",
+                transform.translation,
+                expected.translation
+            );
+            assert!(transform.rotation.abs_diff_eq(expected.rotation, 1e-3));
+            assert!(matches!(
+                app.world().resource::<CameraPathState>(),
+                CameraPathState::Playing { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn playback_ends_at_the_last_keyframe() {
+        let path = path();
+        let (transform, mut app) = play(&path, 0.5, 8);
+        let last = path.keyframes.last().unwrap();
+        assert!(transform.translation.abs_diff_eq(last.translation, 1e-5));
+        assert!(matches!(
+            app.world().resource::<CameraPathState>(),
+            CameraPathState::Idle
+        ));
+        let mut cameras = app.world_mut().query::<&CameraController>();
+        assert!(cameras.single(app.world()).enabled);
+    }
+
+    #[test]
+    fn loaded_keyframes_are_sorted_by_time() {
+        let mut shuffled = path();
+        shuffled.keyframes.reverse();
+        shuffled.keyframes.push(shuffled.keyframes[0]);
+        let file = std::env::temp_dir().join(format!("camera-path-{}.ron", std::process::id()));
+        shuffled.save(&file).unwrap();
+        let loaded = CameraPath::load(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(loaded, path());
+    }
+
+    #[test]
+    fn path_round_trips_through_ron() {
+        let path = path();
+        let text = ron::to_string(&path).unwrap();
+        assert_eq!(ron::from_str::<CameraPath>(&text).unwrap(), path);
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::Write;
+
+use bevy::prelude::*;
+
+use crate::bevy_basic_camera::{CameraController, CameraMode};
+use crate::input_map::{Action, ActionInput, AxisAction, InputMap};
+
+const ACTIONS: [(Action, &str); 23] = [
+    (Action::MoveForward, "Forward"),
+    (Action::MoveBack, "Backward"),
+    (Action::MoveLeft, "Left"),
+    (Action::MoveRight, "Right"),
+    (Action::MoveUp, "Up"),
+    (Action::MoveDown, "Down"),
+    (Action::Run, "Run"),
+    (Action::Jump, "Jump (first/third person)"),
+    (Action::Look, "Mouse look while held"),
+    (Action::ToggleLook, "Toggle mouse look"),
+    (Action::ReleaseCursor, "Release cursor"),
+    (Action::Pan, "Pan (orbit)"),
+    (Action::FocusPivot, "Focus pivot on block (orbit)"),
+    (Action::CycleCameraMode, "Cycle camera mode"),
+    (Action::RecordCameraPath, "Record camera path"),
+    (Action::PlayCameraPath, "Play camera path"),
+    (Action::ExportTerrain, "Export terrain"),
+    (Action::Debug, "Debug overlay"),
+    (Action::ToggleWireframe, "Wireframe"),
+    (Action::ToggleChunkBorders, "Chunk borders"),
+    (Action::CycleChunkColoring, "Chunk coloring"),
+    (Action::ToggleNormals, "Normals under cursor"),
+    (Action::ToggleControls, "Show/hide controls"),
+];
+
+const AXES: [(AxisAction, &str); 6] = [
+    (AxisAction::MoveX, "Move sideways"),
+    (AxisAction::MoveY, "Move up/down"),
+    (AxisAction::MoveZ, "Move forward/back"),
+    (AxisAction::LookX, "Turn"),
+    (AxisAction::LookY, "Look up/down"),
+    (AxisAction::OrbitZoom, "Zoom (orbit, third person)"),
+];
+
+/// Lists the bindings of the [`InputMap`] resource in a panel toggled with
+/// [`Action::ToggleControls`], rebuilt whenever the bindings or the camera mode change
+pub struct ControlsOverlayPlugin;
+
+impl Plugin for ControlsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_controls_overlay)
+            .add_systems(Update, (toggle_controls_overlay, update_controls_overlay));
+    }
+}
+
+#[derive(Component)]
+pub struct ControlsOverlay;
+
+#[derive(Component)]
+struct ControlsText;
+
+fn spawn_controls_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(8.0),
+                    right: Val::Px(8.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            ControlsOverlay,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                ),
+                ControlsText,
+            ));
+        });
+}
+
+fn toggle_controls_overlay(
+    actions: ActionInput,
+    mut overlays: Query<&mut Visibility, With<ControlsOverlay>>,
+) {
+    if !actions.just_pressed(Action::ToggleControls) {
+        return;
+    }
+    for mut visibility in &mut overlays {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn update_controls_overlay(
+    input_map: Res<InputMap>,
+    cameras: Query<&CameraController>,
+    added: Query<(), Added<ControlsText>>,
+    mut texts: Query<&mut Text, With<ControlsText>>,
+    mut shown_mode: Local<Option<CameraMode>>,
+) {
+    let mode = cameras.get_single().ok().map(|camera| camera.mode);
+    if added.is_empty() && !input_map.is_changed() && *shown_mode == mode {
+        return;
+    }
+    *shown_mode = mode;
+
+    let value = controls_text(&input_map, mode);
+    for mut text in &mut texts {
+        text.sections[0].value.clone_from(&value);
+    }
+}
+
+/// The panel contents, one binding per line
+pub fn controls_text(input_map: &InputMap, mode: Option<CameraMode>) -> String {
+    let mut text = String::from("Controls\n");
+    if let Some(mode) = mode {
+        writeln!(text, "Camera: {}", mode.name()).unwrap();
+    }
+    text.push('\n');
+
+    for (action, description) in ACTIONS {
+        let bindings = input_map.bindings(action);
+        writeln!(text, "{} - {description}", join(bindings)).unwrap();
+    }
+    writeln!(text, "Scroll - Zoom (orbit, third person)").unwrap();
+
+    let axes: Vec<_> = AXES
+        .iter()
+        .filter(|(axis, _)| !input_map.axis_bindings(*axis).is_empty())
+        .collect();
+    if !axes.is_empty() {
+        text.push('\n');
+        for (axis, description) in axes {
+            writeln!(
+                text,
+                "{} - {description}",
+                join(input_map.axis_bindings(*axis))
+            )
+            .unwrap();
+        }
+    }
+
+    text.truncate(text.trim_end().len());
+    text
+}
+
+fn join(bindings: &[impl ToString]) -> String {
+    if bindings.is_empty() {
+        return "Unbound".to_string();
+    }
+    bindings
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_map::InputBinding;
+
+    #[test]
+    fn controls_text_follows_rebinding() {
+        let mut input_map = InputMap::default();
+        let text = controls_text(&input_map, Some(CameraMode::Orbit));
+        assert!(text.contains("Camera: orbit"));
+        assert!(text.contains("KeyW - Forward"));
+        assert!(text.contains("MouseMiddle - Pan (orbit)"));
+        assert!(text.contains("GamepadLeftStickX - Move sideways"));
+
+        input_map.rebind(
+            Action::MoveForward,
+            vec![InputBinding::Key(KeyCode::ArrowUp)],
+        );
+        input_map.rebind(Action::Pan, Vec::new());
+        let text = controls_text(&input_map, None);
+        assert!(text.contains("ArrowUp - Forward"));
+        assert!(text.contains("Unbound - Pan (orbit)"));
+        assert!(!text.contains("Camera:"));
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt::Write;
+
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::bevy_basic_camera::CameraController;
+use crate::input_map::{Action, ActionInput};
+use crate::world_generator::block::BlockRegistry;
+use crate::world_generator::chunk::{BlockId, ChunkData, CHUNK_VOLUME, SECTION_COUNT};
+use crate::world_generator::coords::BlockPos;
+use crate::world_generator::render::ChunkMesh;
+use crate::world_generator::world_generator::{PendingChunks, VoxelWorld};
+
+/// How far ahead of the camera the targeted block is looked up
+const TARGET_DISTANCE: f32 = 64.0;
+
+/// F3-style overlay with frame timings, camera position and world statistics,
+/// toggled with [`Action::Debug`]
+pub struct DebugHudPlugin;
+
+impl Plugin for DebugHudPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        app.add_systems(Startup, spawn_debug_hud)
+            .add_systems(Update, (toggle_debug_hud, update_debug_hud).chain());
+    }
+}
+
+#[derive(Component)]
+pub struct DebugHud;
+
+#[derive(Component)]
+struct DebugHudText;
+
+fn spawn_debug_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(8.0),
+                    left: Val::Px(8.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DebugHud,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                ),
+                DebugHudText,
+            ));
+        });
+}
+
+fn toggle_debug_hud(actions: ActionInput, mut huds: Query<&mut Visibility, With<DebugHud>>) {
+    if !actions.just_pressed(Action::Debug) {
+        return;
+    }
+    for mut visibility in &mut huds {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_debug_hud(
+    diagnostics: Res<DiagnosticsStore>,
+    world: Option<Res<VoxelWorld>>,
+    pending: Option<Res<PendingChunks>>,
+    registry: Option<Res<BlockRegistry>>,
+    meshes: Res<Assets<Mesh>>,
+    chunk_meshes: Query<&Handle<Mesh>, With<ChunkMesh>>,
+    cameras: Query<(&GlobalTransform, &CameraController)>,
+    huds: Query<&Visibility, With<DebugHud>>,
+    mut texts: Query<&mut Text, With<DebugHudText>>,
+) {
+    // Nothing is gathered while the overlay is hidden
+    if huds
+        .iter()
+        .all(|visibility| *visibility == Visibility::Hidden)
+    {
+        return;
+    }
+
+    let mut text = String::new();
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed());
+    let frame_time = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed());
+    writeln!(
+        text,
+        "{:.0} fps ({:.2} ms)",
+        fps.unwrap_or_default(),
+        frame_time.unwrap_or_default()
+    )
+    .unwrap();
+
+    if let Ok((transform, camera)) = cameras.get_single() {
+        let position = transform.translation();
+        let block = BlockPos::containing(position);
+        let chunk = block.chunk();
+        writeln!(
+            text,
+            "XYZ: {:.2} / {:.2} / {:.2}",
+            position.x, position.y, position.z
+        )
+        .unwrap();
+        writeln!(text, "Block: {block}").unwrap();
+        writeln!(text, "Chunk: {chunk} (local {})", block.local()).unwrap();
+        if let Some(data) = world.as_deref().and_then(|world| world.chunks.get(&chunk)) {
+            let uniform = data
+                .sections()
+                .iter()
+                .filter(|section| section.uniform().is_some())
+                .count();
+            writeln!(
+                text,
+                "Chunk memory: {} ({uniform}/{SECTION_COUNT} sections uniform)",
+                format_bytes(data.memory_usage())
+            )
+            .unwrap();
+        }
+        writeln!(
+            text,
+            "Facing: {} (yaw {:.1}, pitch {:.1})",
+            facing_name(*transform.forward()),
+            camera.yaw.to_degrees(),
+            camera.pitch.to_degrees()
+        )
+        .unwrap();
+        writeln!(text, "Camera: {}", camera.mode.name()).unwrap();
+
+        if let Some(world) = world.as_deref() {
+            match world.raycast(position, *transform.forward(), TARGET_DISTANCE) {
+                Some(hit) => {
+                    let id = world.block_at(hit.block).unwrap_or_default();
+                    let name = registry
+                        .as_deref()
+                        .and_then(|registry| registry.get(id))
+                        .map_or("unknown", |block| block.display_name.as_str());
+                    writeln!(
+                        text,
+                        "Target: {name} at {} ({:.1} m)",
+                        hit.block, hit.distance
+                    )
+                    .unwrap();
+                }
+                None => writeln!(text, "Target: none").unwrap(),
+            }
+        }
+    }
+
+    text.push('\n');
+    if let Some(world) = &world {
+        writeln!(text, "Loaded chunks: {}", world.chunks.len()).unwrap();
+        let memory: usize = world.chunks.values().map(ChunkData::memory_usage).sum();
+        writeln!(
+            text,
+            "Block memory: {} ({} per chunk, {} dense)",
+            format_bytes(memory),
+            format_bytes(memory / world.chunks.len().max(1)),
+            format_bytes(world.chunks.len() * CHUNK_VOLUME * size_of::<BlockId>())
+        )
+        .unwrap();
+    }
+    if let Some(pending) = &pending {
+        writeln!(text, "Pending tasks: {}", pending.len()).unwrap();
+    }
+    let (vertices, triangles) = chunk_meshes
+        .iter()
+        .filter_map(|handle| meshes.get(handle))
+        .fold((0, 0), |(vertices, triangles), mesh| {
+            let indices = mesh.indices().map_or(0, |indices| indices.len());
+            (vertices + mesh.count_vertices(), triangles + indices / 3)
+        });
+    writeln!(text, "Terrain: {vertices} vertices, {triangles} triangles").unwrap();
+
+    let text = text.trim_end();
+    for mut hud in &mut texts {
+        if hud.sections[0].value != text {
+            hud.sections[0].value = text.to_string();
+        }
+    }
+}
+
+/// Byte count in B, KiB or MiB
+pub fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f32 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f32 / 1_048_576.0),
+    }
+}
+
+/// Compass direction of a view vector, with north towards -Z
+pub fn facing_name(forward: Vec3) -> &'static str {
+    const NAMES: [&str; 8] = [
+        "north",
+        "north-east",
+        "east",
+        "south-east",
+        "south",
+        "south-west",
+        "west",
+        "north-west",
+    ];
+    let angle = forward.x.atan2(-forward.z).to_degrees().rem_euclid(360.0);
+    NAMES[((angle + 22.5) / 45.0) as usize % NAMES.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn facing_name_uses_negative_z_as_north() {
+        assert_eq!(facing_name(Vec3::NEG_Z), "north");
+        assert_eq!(facing_name(Vec3::X), "east");
+        assert_eq!(facing_name(Vec3::Z), "south");
+        assert_eq!(facing_name(Vec3::new(-1.0, -0.5, -1.0)), "north-west");
+    }
+
+    #[test]
+    fn format_bytes_picks_a_unit() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1_048_576), "3.0 MiB");
+    }
+}
@@ -0,0 +1,282 @@
+use bevy::color::palettes::css::{GRAY, LIME, ORANGE, RED, WHITE, YELLOW};
+use bevy::pbr::wireframe::{WireframeConfig, WireframePlugin};
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::utils::{HashMap, HashSet};
+use bevy::window::PrimaryWindow;
+
+use crate::bevy_basic_camera::{cursor_ray, CameraController};
+use crate::input_map::{Action, ActionInput};
+use crate::world_generator::chunk::CHUNK_SIZE;
+use crate::world_generator::coords::ChunkPos;
+use crate::world_generator::material::VoxelWireframeMaterial;
+use crate::world_generator::mesher::{PackedVertex, ATTRIBUTE_VOXEL};
+use crate::world_generator::render::ChunkMesh;
+use crate::world_generator::world_generator::{PendingChunks, VoxelWorld};
+
+/// How far the cursor ray looks for a chunk to draw normals for
+const NORMALS_PICK_DISTANCE: f32 = 256.0;
+const NORMAL_LENGTH: f32 = 0.4;
+
+/// What chunk borders and chunk wireframes are colored by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChunkColoring {
+    /// Everything in one color
+    #[default]
+    Off,
+    /// Pending, generated without faces to mesh, or meshed. Wireframes only exist on meshed
+    /// chunks, so they show pending or meshed.
+    State,
+    /// Distance band from the camera, one band per [`DebugRenderSettings::distance_band`] chunks
+    Distance,
+}
+
+impl ChunkColoring {
+    pub fn next(self) -> Self {
+        match self {
+            ChunkColoring::Off => ChunkColoring::State,
+            ChunkColoring::State => ChunkColoring::Distance,
+            ChunkColoring::Distance => ChunkColoring::Off,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct DebugRenderSettings {
+    pub wireframe: bool,
+    pub chunk_borders: bool,
+    pub coloring: ChunkColoring,
+    /// Width in chunks of each band in [`ChunkColoring::Distance`]
+    pub distance_band: u32,
+    pub normals: bool,
+}
+
+impl Default for DebugRenderSettings {
+    fn default() -> Self {
+        Self {
+            wireframe: false,
+            chunk_borders: false,
+            coloring: ChunkColoring::Off,
+            distance_band: 2,
+            normals: false,
+        }
+    }
+}
+
+/// Debug drawing of the terrain, switched at runtime with the debug actions:
+/// wireframes, chunk boundary boxes colored by [`ChunkColoring`], and the vertex normals of
+/// the chunk under the cursor
+pub struct DebugRenderPlugin;
+
+impl Plugin for DebugRenderPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WireframePlugin>() {
+            app.add_plugins(WireframePlugin);
+        }
+        app.init_resource::<DebugRenderSettings>()
+            .insert_resource(WireframeConfig {
+                global: false,
+                default_color: WHITE.into(),
+            })
+            .add_systems(
+                Update,
+                (
+                    toggle_debug_render,
+                    (color_chunk_wireframes, draw_chunk_borders, draw_normals),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn toggle_debug_render(
+    actions: ActionInput,
+    mut settings: ResMut<DebugRenderSettings>,
+    mut wireframe: ResMut<WireframeConfig>,
+) {
+    if actions.just_pressed(Action::ToggleWireframe) {
+        settings.wireframe = !settings.wireframe;
+    }
+    if actions.just_pressed(Action::ToggleChunkBorders) {
+        settings.chunk_borders = !settings.chunk_borders;
+    }
+    if actions.just_pressed(Action::CycleChunkColoring) {
+        settings.coloring = settings.coloring.next();
+        info!("Chunk coloring: {:?}", settings.coloring);
+    }
+    if actions.just_pressed(Action::ToggleNormals) {
+        settings.normals = !settings.normals;
+    }
+    if wireframe.global != settings.wireframe {
+        wireframe.global = settings.wireframe;
+    }
+}
+
+/// Color of a chunk in the current [`ChunkColoring`]
+fn chunk_color(
+    settings: &DebugRenderSettings,
+    pos: ChunkPos,
+    meshed: bool,
+    pending: bool,
+    camera_chunk: ChunkPos,
+) -> Color {
+    const BAND_COLORS: [Srgba; 4] = [LIME, YELLOW, ORANGE, RED];
+
+    match settings.coloring {
+        ChunkColoring::Off => WHITE.into(),
+        ChunkColoring::State if pending => RED.into(),
+        ChunkColoring::State if meshed => LIME.into(),
+        ChunkColoring::State => YELLOW.into(),
+        ChunkColoring::Distance => {
+            let distance = (pos.0 - camera_chunk.0).abs().max_element() as u32;
+            let band = distance / settings.distance_band.max(1);
+            BAND_COLORS[(band as usize).min(BAND_COLORS.len() - 1)].into()
+        }
+    }
+}
+
+fn camera_chunk(cameras: &Query<&GlobalTransform, With<CameraController>>) -> ChunkPos {
+    let position = cameras
+        .get_single()
+        .map_or(Vec3::ZERO, GlobalTransform::translation);
+    ChunkPos::containing(position)
+}
+
+/// Chunk meshes have no position attribute for Bevy's wireframes, so they get a
+/// [`VoxelWireframeMaterial`] on top instead, one per color
+fn color_chunk_wireframes(
+    settings: Res<DebugRenderSettings>,
+    pending: Option<Res<PendingChunks>>,
+    cameras: Query<&GlobalTransform, With<CameraController>>,
+    chunks: Query<(Entity, &ChunkMesh, Option<&Handle<VoxelWireframeMaterial>>)>,
+    mut materials: ResMut<Assets<VoxelWireframeMaterial>>,
+    mut by_color: Local<HashMap<[u8; 4], Handle<VoxelWireframeMaterial>>>,
+    mut commands: Commands,
+) {
+    if !settings.wireframe {
+        for (entity, _, material) in &chunks {
+            if material.is_some() {
+                commands
+                    .entity(entity)
+                    .remove::<Handle<VoxelWireframeMaterial>>();
+            }
+        }
+        return;
+    }
+
+    let camera_chunk = camera_chunk(&cameras);
+    for (entity, &ChunkMesh(pos), current) in &chunks {
+        // A meshed chunk is pending again while it regenerates, and never without faces
+        let pending = pending
+            .as_ref()
+            .is_some_and(|pending| pending.contains(pos));
+        let color = chunk_color(&settings, pos, true, pending, camera_chunk);
+        let material = by_color
+            .entry(color.to_srgba().to_u8_array())
+            .or_insert_with(|| {
+                materials.add(VoxelWireframeMaterial {
+                    color: color.into(),
+                })
+            });
+        if current != Some(material) {
+            commands.entity(entity).insert(material.clone());
+        }
+    }
+}
+
+fn draw_chunk_borders(
+    settings: Res<DebugRenderSettings>,
+    world: Option<Res<VoxelWorld>>,
+    pending: Option<Res<PendingChunks>>,
+    chunk_meshes: Query<&ChunkMesh>,
+    cameras: Query<&GlobalTransform, With<CameraController>>,
+    mut gizmos: Gizmos,
+) {
+    if !settings.chunk_borders {
+        return;
+    }
+    let camera_chunk = camera_chunk(&cameras);
+    let meshed: HashSet<ChunkPos> = chunk_meshes.iter().map(|chunk| chunk.0).collect();
+    let size = Vec3::splat(CHUNK_SIZE as f32);
+
+    let mut draw = |pos: ChunkPos, pending: bool| {
+        let color = chunk_color(&settings, pos, meshed.contains(&pos), pending, camera_chunk);
+        let transform =
+            Transform::from_translation(pos.origin().as_vec3() + size / 2.0).with_scale(size);
+        gizmos.cuboid(transform, color);
+    };
+    if let Some(world) = &world {
+        for &pos in world.chunks.keys() {
+            if pending
+                .as_ref()
+                .is_some_and(|pending| pending.contains(pos))
+            {
+                continue;
+            }
+            draw(pos, false);
+        }
+    }
+    if let Some(pending) = &pending {
+        for pos in pending.positions() {
+            draw(pos, true);
+        }
+    }
+}
+
+fn draw_normals(
+    settings: Res<DebugRenderSettings>,
+    world: Option<Res<VoxelWorld>>,
+    meshes: Res<Assets<Mesh>>,
+    chunk_meshes: Query<(&ChunkMesh, &Handle<Mesh>)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<CameraController>>,
+    mut gizmos: Gizmos,
+) {
+    if !settings.normals {
+        return;
+    }
+    let Some(world) = world else {
+        return;
+    };
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let Some(ray) = cursor_ray(window, camera, camera_transform) else {
+        return;
+    };
+    let Some(hit) = world.raycast(ray.origin, *ray.direction, NORMALS_PICK_DISTANCE) else {
+        return;
+    };
+
+    let target = hit.block.chunk();
+    let Some(mesh) = chunk_meshes
+        .iter()
+        .find(|(chunk, _)| chunk.0 == target)
+        .and_then(|(_, handle)| meshes.get(handle))
+    else {
+        return;
+    };
+    let Some(VertexAttributeValues::Uint32x2(vertices)) = mesh.attribute(ATTRIBUTE_VOXEL) else {
+        return;
+    };
+
+    let origin = target.origin().as_vec3();
+    for vertex in vertices.iter().map(|packed| PackedVertex::unpack(*packed)) {
+        let start = origin + vertex.position.as_vec3();
+        let normal = vertex.face.normal().as_vec3();
+        gizmos.line(
+            start,
+            start + normal * NORMAL_LENGTH,
+            Color::srgb(
+                normal.x.abs().max(0.2),
+                normal.y.abs().max(0.2),
+                normal.z.abs().max(0.2),
+            ),
+        );
+    }
+    gizmos.cuboid(
+        Transform::from_translation(hit.block.center()).with_scale(Vec3::splat(1.01)),
+        GRAY,
+    );
+}
@@ -0,0 +1,495 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const INPUT_CONFIG_PATH: &str = "assets/config/input.ron";
+
+/// Everything the player can do, independent of the physical input that triggers it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Run,
+    /// Mouse look while held
+    Look,
+    /// Toggles mouse look without holding [`Action::Look`]
+    ToggleLook,
+    /// Turns mouse look off and frees the cursor
+    ReleaseCursor,
+    /// Drags the orbit pivot across the view while held
+    Pan,
+    /// Moves the orbit pivot to the block under the cursor
+    FocusPivot,
+    /// Switches to the next [`CameraMode`](crate::bevy_basic_camera::CameraMode)
+    CycleCameraMode,
+    /// Starts or stops recording the camera path
+    RecordCameraPath,
+    /// Starts or stops playing back the recorded camera path
+    PlayCameraPath,
+    /// Shows or hides the controls panel
+    ToggleControls,
+    /// Draws every mesh as wireframe
+    ToggleWireframe,
+    /// Draws the boundary box of every chunk
+    ToggleChunkBorders,
+    /// Switches how chunk borders and wireframes are colored
+    CycleChunkColoring,
+    /// Draws the vertex normals of the chunk under the cursor
+    ToggleNormals,
+    Jump,
+    /// Nothing handles block interaction yet, so this is unbound by default
+    Break,
+    /// Unbound by default, like [`Action::Break`]
+    Place,
+    /// Unbound by default, like [`Action::Break`]
+    Inventory,
+    Debug,
+    ExportTerrain,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Matches the button on any connected gamepad
+    Gamepad(GamepadButtonType),
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputBinding::Key(key) => write!(f, "{key:?}"),
+            InputBinding::Mouse(button) => write!(f, "Mouse{button:?}"),
+            InputBinding::Gamepad(button) => write!(f, "Gamepad{button:?}"),
+        }
+    }
+}
+
+/// Analog inputs, each read as a value between -1 and 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AxisAction {
+    MoveX,
+    MoveY,
+    MoveZ,
+    LookX,
+    LookY,
+    /// Replaces forward movement while the camera orbits
+    OrbitZoom,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AxisBinding {
+    /// A stick axis on any connected gamepad
+    GamepadAxis(GamepadAxisType),
+    /// Two analog buttons such as the triggers, the negative one is subtracted from the positive
+    GamepadButtons {
+        positive: GamepadButtonType,
+        negative: GamepadButtonType,
+    },
+}
+
+impl fmt::Display for AxisBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AxisBinding::GamepadAxis(axis) => write!(f, "Gamepad{axis:?}"),
+            AxisBinding::GamepadButtons { positive, negative } => {
+                write!(f, "Gamepad{positive:?}/Gamepad{negative:?}")
+            }
+        }
+    }
+}
+
+/// Shapes raw analog values, keeping the sign
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    Quadratic,
+    Cubic,
+    Power(f32),
+}
+
+impl ResponseCurve {
+    pub fn apply(self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        let shaped = match self {
+            ResponseCurve::Linear => magnitude,
+            ResponseCurve::Quadratic => magnitude * magnitude,
+            ResponseCurve::Cubic => magnitude * magnitude * magnitude,
+            ResponseCurve::Power(exponent) => magnitude.powf(exponent),
+        };
+        shaped.copysign(value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisResponse {
+    /// Values below this magnitude read as zero, the rest is rescaled to start at zero
+    pub dead_zone: f32,
+    pub curve: ResponseCurve,
+}
+
+impl AxisResponse {
+    pub fn apply(&self, value: f32) -> f32 {
+        let dead_zone = self.dead_zone.clamp(0.0, 0.99);
+        let magnitude = value.abs();
+        if magnitude <= dead_zone {
+            return 0.0;
+        }
+        let rescaled = ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0);
+        self.curve.apply(rescaled).copysign(value)
+    }
+
+    fn trigger() -> Self {
+        Self {
+            dead_zone: 0.05,
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+impl Default for AxisResponse {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.15,
+            curve: ResponseCurve::Quadratic,
+        }
+    }
+}
+
+/// Maps every [`Action`] and [`AxisAction`] to the inputs that trigger it.
+/// Loaded from [`INPUT_CONFIG_PATH`] at startup and can be rebound at runtime.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<InputBinding>>,
+    #[serde(default)]
+    axes: BTreeMap<AxisAction, Vec<AxisBinding>>,
+    /// Applied to [`AxisBinding::GamepadAxis`]
+    #[serde(default)]
+    pub stick_response: AxisResponse,
+    /// Applied to [`AxisBinding::GamepadButtons`]
+    #[serde(default = "AxisResponse::trigger")]
+    pub trigger_response: AxisResponse,
+}
+
+impl InputMap {
+    pub fn empty() -> Self {
+        Self {
+            bindings: BTreeMap::new(),
+            axes: BTreeMap::new(),
+            stick_response: AxisResponse::default(),
+            trigger_response: AxisResponse::trigger(),
+        }
+    }
+
+    pub fn with(mut self, action: Action, binding: InputBinding) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    pub fn bind(&mut self, action: Action, binding: InputBinding) {
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: Action, binding: InputBinding) {
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            bindings.retain(|bound| *bound != binding);
+        }
+    }
+
+    /// Replaces every binding of an action
+    pub fn rebind(&mut self, action: Action, bindings: Vec<InputBinding>) {
+        self.bindings.insert(action, bindings);
+    }
+
+    pub fn bindings(&self, action: Action) -> &[InputBinding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn with_axis(mut self, action: AxisAction, binding: AxisBinding) -> Self {
+        self.bind_axis(action, binding);
+        self
+    }
+
+    pub fn bind_axis(&mut self, action: AxisAction, binding: AxisBinding) {
+        let bindings = self.axes.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replaces every binding of an axis
+    pub fn rebind_axis(&mut self, action: AxisAction, bindings: Vec<AxisBinding>) {
+        self.axes.insert(action, bindings);
+    }
+
+    pub fn axis_bindings(&self, action: AxisAction) -> &[AxisBinding] {
+        self.axes.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Action, &[InputBinding])> {
+        self.bindings
+            .iter()
+            .map(|(action, bindings)| (*action, bindings.as_slice()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        std::fs::write(path, text)
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use InputBinding::*;
+
+        InputMap::empty()
+            .with(Action::MoveForward, Key(KeyCode::KeyW))
+            .with(Action::MoveBack, Key(KeyCode::KeyS))
+            .with(Action::MoveLeft, Key(KeyCode::KeyA))
+            .with(Action::MoveRight, Key(KeyCode::KeyD))
+            .with(Action::MoveUp, Key(KeyCode::KeyE))
+            .with(Action::MoveDown, Key(KeyCode::KeyQ))
+            .with(Action::Run, Key(KeyCode::ShiftLeft))
+            .with(Action::Run, Gamepad(GamepadButtonType::LeftThumb))
+            .with(Action::Look, Mouse(MouseButton::Left))
+            .with(Action::ToggleLook, Key(KeyCode::KeyM))
+            .with(Action::ReleaseCursor, Key(KeyCode::Escape))
+            .with(Action::Pan, Mouse(MouseButton::Middle))
+            .with(Action::FocusPivot, Mouse(MouseButton::Right))
+            .with(Action::CycleCameraMode, Key(KeyCode::F5))
+            .with(Action::RecordCameraPath, Key(KeyCode::F6))
+            .with(Action::PlayCameraPath, Key(KeyCode::F7))
+            .with(Action::ToggleControls, Key(KeyCode::F1))
+            .with(Action::ToggleWireframe, Key(KeyCode::F2))
+            .with(Action::ToggleChunkBorders, Key(KeyCode::F4))
+            .with(Action::CycleChunkColoring, Key(KeyCode::F8))
+            .with(Action::ToggleNormals, Key(KeyCode::F10))
+            .with(Action::Jump, Key(KeyCode::Space))
+            .with(Action::Jump, Gamepad(GamepadButtonType::South))
+            .with(Action::Debug, Key(KeyCode::F3))
+            .with(Action::ExportTerrain, Key(KeyCode::F9))
+            .with_axis(
+                AxisAction::MoveX,
+                AxisBinding::GamepadAxis(GamepadAxisType::LeftStickX),
+            )
+            .with_axis(
+                AxisAction::MoveY,
+                AxisBinding::GamepadButtons {
+                    positive: GamepadButtonType::RightTrigger2,
+                    negative: GamepadButtonType::LeftTrigger2,
+                },
+            )
+            .with_axis(
+                AxisAction::MoveZ,
+                AxisBinding::GamepadAxis(GamepadAxisType::LeftStickY),
+            )
+            .with_axis(
+                AxisAction::LookX,
+                AxisBinding::GamepadAxis(GamepadAxisType::RightStickX),
+            )
+            .with_axis(
+                AxisAction::LookY,
+                AxisBinding::GamepadAxis(GamepadAxisType::RightStickY),
+            )
+            .with_axis(
+                AxisAction::OrbitZoom,
+                AxisBinding::GamepadAxis(GamepadAxisType::LeftStickY),
+            )
+    }
+}
+
+/// Reads actions through the [`InputMap`] instead of raw inputs
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    map: Res<'w, InputMap>,
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse_buttons: Res<'w, ButtonInput<MouseButton>>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+    gamepad_button_axes: Res<'w, Axis<GamepadButton>>,
+}
+
+impl ActionInput<'_> {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.check(
+            action,
+            ButtonInput::pressed,
+            ButtonInput::pressed,
+            ButtonInput::pressed,
+        )
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.check(
+            action,
+            ButtonInput::just_pressed,
+            ButtonInput::just_pressed,
+            ButtonInput::just_pressed,
+        )
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.check(
+            action,
+            ButtonInput::just_released,
+            ButtonInput::just_released,
+            ButtonInput::just_released,
+        )
+    }
+
+    /// Sum of every binding of the axis on every gamepad, shaped by the map's response
+    /// settings and clamped to -1..=1
+    pub fn axis(&self, action: AxisAction) -> f32 {
+        let mut value = 0.0;
+        for &binding in self.map.axis_bindings(action) {
+            for gamepad in self.gamepads.iter() {
+                value += match binding {
+                    AxisBinding::GamepadAxis(axis_type) => {
+                        let raw = self
+                            .gamepad_axes
+                            .get(GamepadAxis::new(gamepad, axis_type))
+                            .unwrap_or(0.0);
+                        self.map.stick_response.apply(raw)
+                    }
+                    AxisBinding::GamepadButtons { positive, negative } => {
+                        let button = |button_type| {
+                            self.gamepad_button_axes
+                                .get(GamepadButton::new(gamepad, button_type))
+                                .unwrap_or(0.0)
+                        };
+                        self.map
+                            .trigger_response
+                            .apply(button(positive) - button(negative))
+                    }
+                };
+            }
+        }
+        value.clamp(-1.0, 1.0)
+    }
+
+    pub fn map(&self) -> &InputMap {
+        &self.map
+    }
+
+    fn check(
+        &self,
+        action: Action,
+        key: fn(&ButtonInput<KeyCode>, KeyCode) -> bool,
+        mouse: fn(&ButtonInput<MouseButton>, MouseButton) -> bool,
+        gamepad: fn(&ButtonInput<GamepadButton>, GamepadButton) -> bool,
+    ) -> bool {
+        self.map
+            .bindings(action)
+            .iter()
+            .any(|&binding| match binding {
+                InputBinding::Key(code) => key(&self.keys, code),
+                InputBinding::Mouse(button) => mouse(&self.mouse_buttons, button),
+                InputBinding::Gamepad(button_type) => self.gamepads.iter().any(|pad| {
+                    gamepad(&self.gamepad_buttons, GamepadButton::new(pad, button_type))
+                }),
+            })
+    }
+}
+
+/// Loads the [`InputMap`] from a RON file, falling back to the default bindings
+pub struct InputMapPlugin {
+    pub path: PathBuf,
+}
+
+impl Default for InputMapPlugin {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from(INPUT_CONFIG_PATH),
+        }
+    }
+}
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        let input_map = InputMap::load(&self.path).unwrap_or_else(|error| {
+            warn!(
+                "Using default input bindings, could not load {}: {error}",
+                self.path.display()
+            );
+            InputMap::default()
+        });
+        app.insert_resource(input_map);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_config_matches_defaults() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(INPUT_CONFIG_PATH);
+        assert_eq!(InputMap::load(path).unwrap(), InputMap::default());
+    }
+
+    #[test]
+    fn default_bindings_do_not_overlap() {
+        let input_map = InputMap::default();
+        let mut seen = Vec::new();
+        for (action, bindings) in input_map.iter() {
+            for binding in bindings {
+                assert!(
+                    !seen.contains(binding),
+                    "{binding} is bound to {action:?} and another action"
+                );
+                seen.push(*binding);
+            }
+        }
+    }
+
+    #[test]
+    fn bindings_round_trip_through_ron() {
+        let mut input_map = InputMap::default();
+        input_map.rebind(Action::Jump, vec![InputBinding::Key(KeyCode::KeyJ)]);
+        input_map.unbind(Action::Run, InputBinding::Key(KeyCode::ShiftLeft));
+
+        let text = ron::to_string(&input_map).unwrap();
+        assert_eq!(ron::from_str::<InputMap>(&text).unwrap(), input_map);
+    }
+
+    #[test]
+    fn dead_zone_is_rescaled_and_keeps_sign() {
+        let response = AxisResponse {
+            dead_zone: 0.2,
+            curve: ResponseCurve::Linear,
+        };
+        assert_eq!(response.apply(0.1), 0.0);
+        assert_eq!(response.apply(-0.2), 0.0);
+        assert!((response.apply(0.6) - 0.5).abs() < 1e-6);
+        assert!((response.apply(-0.6) + 0.5).abs() < 1e-6);
+        assert_eq!(response.apply(1.0), 1.0);
+        assert_eq!(response.apply(-1.5), -1.0);
+
+        let quadratic = AxisResponse {
+            curve: ResponseCurve::Quadratic,
+            ..response
+        };
+        assert!((quadratic.apply(-0.6) + 0.25).abs() < 1e-6);
+    }
+}
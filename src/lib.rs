@@ -0,0 +1,8 @@
+pub mod bevy_basic_camera;
+pub mod camera_path;
+pub mod controls_overlay;
+pub mod debug_hud;
+pub mod debug_render;
+pub mod input_map;
+pub mod player;
+pub mod world_generator;
@@ -1,62 +1,94 @@
-mod world_generator;
-
-use bevy::color::palettes::css::WHITE;
-
-use bevy::pbr::wireframe::{WireframeColor, WireframeConfig, WireframePlugin};
 use bevy::prelude::*;
 
-use bevy::render::mesh::{Indices, PrimitiveTopology};
-use bevy::render::render_asset::RenderAssetUsages;
-use world_generator::world_generator::WorldGeneratorPlugin;
+use bevy_minecraft_clone::bevy_basic_camera::{
+    CameraController, CameraControllerPlugin, CameraMode,
+};
+use bevy_minecraft_clone::camera_path::CameraPathPlugin;
+use bevy_minecraft_clone::controls_overlay::ControlsOverlayPlugin;
+use bevy_minecraft_clone::debug_hud::DebugHudPlugin;
+use bevy_minecraft_clone::debug_render::DebugRenderPlugin;
+use bevy_minecraft_clone::input_map::InputMapPlugin;
+use bevy_minecraft_clone::player::PlayerPlugin;
+use bevy_minecraft_clone::world_generator::block::BlockRegistry;
+use bevy_minecraft_clone::world_generator::config::WorldGenConfigPlugin;
+use bevy_minecraft_clone::world_generator::export::TerrainExportPlugin;
+use bevy_minecraft_clone::world_generator::render::WorldRenderPlugin;
+use bevy_minecraft_clone::world_generator::resource_pack::ResourcePackPlugin;
+use bevy_minecraft_clone::world_generator::terrain::GeneratorKind;
+use bevy_minecraft_clone::world_generator::world_generator::{
+    ChunkLoader, WorldGeneratorPlugin, WorldSettings,
+};
 
-mod bevy_basic_camera;
-use bevy_basic_camera::{CameraController, CameraControllerPlugin};
 fn main() {
+    // `--preset <name>` starts with a terrain preset instead of the default config
+    let worldgen_config = match arg("--preset") {
+        Some(name) => WorldGenConfigPlugin::preset(name.parse().unwrap_or_else(exit_with_error)),
+        None => WorldGenConfigPlugin::default(),
+    };
+    // `--generator <kind>` swaps the noise terrain for a superflat, debug or void world
+    let generator = arg("--generator").map_or_else(GeneratorKind::default, |kind| {
+        GeneratorKind::parse(&kind, &BlockRegistry::default()).unwrap_or_else(exit_with_error)
+    });
+
     App::new()
         .add_plugins((
-            DefaultPlugins.set(ImagePlugin::default_nearest()),
-            WireframePlugin,
+            DefaultPlugins,
+            InputMapPlugin::default(),
             WorldGeneratorPlugin,
+            worldgen_config,
+            WorldRenderPlugin,
+            ResourcePackPlugin,
+            TerrainExportPlugin,
             CameraControllerPlugin,
+            PlayerPlugin::default(),
+            CameraPathPlugin::default(),
+            ControlsOverlayPlugin,
+            DebugHudPlugin,
+            DebugRenderPlugin,
         ))
-        .insert_resource(WireframeConfig {
-            // The global wireframe config enables drawing of wireframes on every mesh,
-            // except those with `NoWireframe`. Meshes with `Wireframe` will always have a wireframe,
-            // regardless of the global configuration.
-            global: true,
-            // Controls the default color of all wireframes. Used as the default color for global wireframes.
-            // Can be changed per mesh using the `WireframeColor` component.
-            default_color: WHITE.into(),
-        }) // Loads default Bevy plugins (rendering, input, etc.)
+        .insert_resource(WorldSettings {
+            generator,
+            ..default()
+        })
         .add_systems(Startup, setup)
         // .add_systems(Update, rotate_cube) // Startup systems for camera and cube
         .run();
 }
 
+/// Value following `name` on the command line
+fn arg(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+fn exit_with_error<T>(error: String) -> T {
+    eprintln!("error: {error}");
+    std::process::exit(2);
+}
+
 fn setup(mut commands: Commands) {
     // Cube
 
     // light
-    commands.spawn(PointLightBundle {
-        transform: Transform::from_xyz(4.0, 8.0, 6.0),
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
         ..default()
     });
 
     // camera
     commands
         .spawn(Camera3dBundle {
-            transform: Transform::from_xyz(3.0, 3.0, 3.0)
-                .looking_at(Vec3::new(0.0, 0.5, 0.0), Vec3::Y),
+            transform: Transform::from_xyz(-40.0, 48.0, -40.0)
+                .looking_at(Vec3::new(16.0, 12.0, 16.0), Vec3::Y),
             ..default()
         })
-        .insert(
+        .insert((
             CameraController {
-                orbit_mode: true,
-                orbit_focus: Vec3::new(0.0, 0.5, 0.0),
+                mode: CameraMode::Orbit,
+                orbit_focus: Vec3::new(16.0, 12.0, 16.0),
                 ..default()
-            }
-            .print_controls(),
-        );
+            },
+            ChunkLoader,
+        ));
 }
 
 // fn rotate_cube(time: Res<Time>, mut query: Query<(&RotatingCube, &mut Transform)>) {
@@ -0,0 +1,364 @@
+use bevy::prelude::*;
+
+use crate::bevy_basic_camera::{approach, camera_controller, CameraController, CameraMode};
+use crate::input_map::{Action, ActionInput, AxisAction};
+use crate::world_generator::block::BlockRegistry;
+use crate::world_generator::chunk::{CHUNK_SIZE, WORLD_MAX_Y, WORLD_MIN_Y};
+use crate::world_generator::coords::BlockPos;
+use crate::world_generator::world_generator::VoxelWorld;
+
+/// Physics body the camera attaches to in [`CameraMode::FirstPerson`] and
+/// [`CameraMode::ThirdPerson`]. The transform sits at the feet, centered on the collision box.
+#[derive(Component, Clone)]
+pub struct Player {
+    pub velocity: Vec3,
+    pub on_ground: bool,
+    /// Half the size of the collision box
+    pub half_extents: Vec3,
+    /// Height of the camera above the feet in first person
+    pub eye_height: f32,
+    pub walk_speed: f32,
+    pub run_speed: f32,
+    /// Rate per second at which the horizontal velocity approaches the target on the ground
+    pub ground_acceleration: f32,
+    /// Same as [`Player::ground_acceleration`] while falling or jumping
+    pub air_acceleration: f32,
+    pub jump_speed: f32,
+    pub gravity: f32,
+}
+
+impl Player {
+    pub fn eye(&self, feet: Vec3) -> Vec3 {
+        feet + Vec3::Y * self.eye_height
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            velocity: Vec3::ZERO,
+            on_ground: false,
+            half_extents: Vec3::new(0.3, 0.9, 0.3),
+            eye_height: 1.62,
+            walk_speed: 4.3,
+            run_speed: 7.0,
+            ground_acceleration: 20.0,
+            air_acceleration: 4.0,
+            jump_speed: 8.0,
+            gravity: 28.0,
+        }
+    }
+}
+
+/// Spawns the [`Player`] body with a capsule mesh and moves it with the movement actions
+/// while the camera is attached to it
+pub struct PlayerPlugin {
+    /// Feet position of the body, it falls onto the terrain once the chunk below is generated
+    pub spawn: Vec3,
+}
+
+impl Default for PlayerPlugin {
+    fn default() -> Self {
+        let center = CHUNK_SIZE as f32 / 2.0;
+        Self {
+            spawn: Vec3::new(center, 2.0 * CHUNK_SIZE as f32, center),
+        }
+    }
+}
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        let spawn = self.spawn;
+        app.add_systems(
+            Startup,
+            move |mut commands: Commands,
+                  mut meshes: ResMut<Assets<Mesh>>,
+                  mut materials: ResMut<Assets<StandardMaterial>>| {
+                spawn_player(&mut commands, &mut meshes, &mut materials, spawn);
+            },
+        )
+        .add_systems(
+            Update,
+            (move_player.before(camera_controller), show_player_body),
+        );
+    }
+}
+
+fn spawn_player(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    feet: Vec3,
+) {
+    let player = Player::default();
+    let radius = player.half_extents.x;
+    let body = commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(feet)),
+            player.clone(),
+        ))
+        .id();
+    let mesh = commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Capsule3d::new(
+                radius,
+                player.half_extents.y * 2.0 - radius * 2.0,
+            )),
+            material: materials.add(Color::srgb(0.2, 0.4, 0.9)),
+            transform: Transform::from_translation(Vec3::Y * player.half_extents.y),
+            ..default()
+        })
+        .id();
+    commands.entity(body).add_child(mesh);
+}
+
+/// Walks, jumps and falls with collision against the voxel world. The body stays frozen while
+/// the chunk it is in has not been generated yet.
+pub fn move_player(
+    time: Res<Time>,
+    actions: ActionInput,
+    world: Option<Res<VoxelWorld>>,
+    registry: Option<Res<BlockRegistry>>,
+    cameras: Query<&CameraController>,
+    mut players: Query<(&mut Transform, &mut Player)>,
+) {
+    let dt = time.delta_seconds();
+    let (Some(world), Some(registry)) = (world, registry) else {
+        return;
+    };
+    let Ok((mut transform, mut player)) = players.get_single_mut() else {
+        return;
+    };
+    let feet = BlockPos::containing(transform.translation);
+    if !world.chunks.contains_key(&feet.chunk()) {
+        return;
+    }
+
+    // Movement input only applies while the camera is attached to the body
+    let camera = cameras
+        .get_single()
+        .ok()
+        .filter(|camera| camera.enabled && camera.mode.has_body());
+    let mut wish = Vec3::ZERO;
+    let mut jump = false;
+    let mut run = false;
+    if let Some(camera) = camera {
+        let mut input = Vec2::ZERO;
+        if actions.pressed(Action::MoveForward) {
+            input.y += 1.0;
+        }
+        if actions.pressed(Action::MoveBack) {
+            input.y -= 1.0;
+        }
+        if actions.pressed(Action::MoveRight) {
+            input.x += 1.0;
+        }
+        if actions.pressed(Action::MoveLeft) {
+            input.x -= 1.0;
+        }
+        input += Vec2::new(
+            actions.axis(AxisAction::MoveX),
+            actions.axis(AxisAction::MoveZ),
+        );
+        let facing = Quat::from_rotation_y(camera.yaw);
+        let input = input.clamp_length_max(1.0);
+        wish = facing * Vec3::new(input.x, 0.0, -input.y);
+        jump = actions.pressed(Action::Jump);
+        run = actions.pressed(Action::Run);
+    }
+
+    let speed = if run {
+        player.run_speed
+    } else {
+        player.walk_speed
+    };
+    let acceleration = if player.on_ground {
+        player.ground_acceleration
+    } else {
+        player.air_acceleration
+    };
+    let horizontal = approach(
+        player.velocity * Vec3::new(1.0, 0.0, 1.0),
+        wish * speed,
+        acceleration,
+        dt,
+    );
+    player.velocity.x = horizontal.x;
+    player.velocity.z = horizontal.z;
+    if jump && player.on_ground {
+        player.velocity.y = player.jump_speed;
+    }
+    player.velocity.y -= player.gravity * dt;
+
+    let half_extents = player.half_extents;
+    let center = transform.translation + Vec3::Y * half_extents.y;
+    let (center, blocked) = move_and_collide(
+        &world,
+        &registry,
+        center,
+        half_extents,
+        player.velocity * dt,
+        true,
+    );
+    transform.translation = center - Vec3::Y * half_extents.y;
+
+    player.on_ground = blocked.y && player.velocity.y < 0.0;
+    player.velocity = Vec3::select(blocked, Vec3::ZERO, player.velocity);
+}
+
+/// Hides the body in first person so it does not block the view
+fn show_player_body(
+    cameras: Query<&CameraController, Changed<CameraController>>,
+    mut players: Query<&mut Visibility, With<Player>>,
+) {
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let visibility = if camera.mode == CameraMode::FirstPerson {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for mut body in &mut players {
+        body.set_if_neq(visibility);
+    }
+}
+
+/// Blocks with collision as defined by the registry, everything above the world is open
+fn is_solid(
+    world: &VoxelWorld,
+    registry: &BlockRegistry,
+    pos: BlockPos,
+    unloaded_is_solid: bool,
+) -> bool {
+    pos.0.y < WORLD_MAX_Y
+        && world
+            .block_at(pos)
+            .map_or(unloaded_is_solid || pos.0.y < WORLD_MIN_Y, |id| {
+                registry.is_solid(id)
+            })
+}
+
+/// Moves a box by `delta` one axis at a time, stopping it flush against solid blocks.
+/// With `unloaded_is_solid` the edge of the generated world acts as a wall.
+/// Returns the new center and the axes along which the movement was blocked.
+pub fn move_and_collide(
+    world: &VoxelWorld,
+    registry: &BlockRegistry,
+    center: Vec3,
+    half_extents: Vec3,
+    delta: Vec3,
+    unloaded_is_solid: bool,
+) -> (Vec3, BVec3) {
+    const SKIN: f32 = 1e-3;
+    // Steps shorter than a block so fast movement cannot tunnel through thin walls
+    let steps = (delta.abs().max_element() / 0.5).ceil().max(1.0);
+    let step = delta / steps;
+
+    let mut center = center;
+    let mut blocked = [false; 3];
+    for _ in 0..steps as usize {
+        // Vertical first, so landing is resolved before sliding along walls
+        for axis in [1, 0, 2] {
+            if step[axis] == 0.0 || blocked[axis] {
+                continue;
+            }
+            center[axis] += step[axis];
+
+            let min = BlockPos::containing(center - half_extents + SKIN).0;
+            let max = BlockPos::containing(center + half_extents - SKIN).0;
+            let mut contact = None;
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    for z in min.z..=max.z {
+                        let block = BlockPos::new(x, y, z);
+                        if !is_solid(world, registry, block, unloaded_is_solid) {
+                            continue;
+                        }
+                        let face = block.0[axis];
+                        contact = Some(match contact {
+                            None => face,
+                            Some(other) if step[axis] > 0.0 => face.min(other),
+                            Some(other) => face.max(other),
+                        });
+                    }
+                }
+            }
+
+            if let Some(face) = contact {
+                center[axis] = if step[axis] > 0.0 {
+                    face as f32 - half_extents[axis]
+                } else {
+                    face as f32 + 1.0 + half_extents[axis]
+                };
+                blocked[axis] = true;
+            }
+        }
+    }
+
+    (center, BVec3::from(blocked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_generator::chunk::{ChunkData, STONE};
+    use crate::world_generator::coords::{ChunkPos, LocalPos};
+
+    fn floor_world(height: usize) -> VoxelWorld {
+        let mut chunk = ChunkData::empty();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..height {
+                    chunk.set(LocalPos::new(x, y, z), STONE);
+                }
+            }
+        }
+        let mut world = VoxelWorld::default();
+        world.chunks.insert(ChunkPos::ZERO, chunk);
+        world
+    }
+
+    #[test]
+    fn falling_box_lands_on_the_floor() {
+        let world = floor_world(10);
+        let half_extents = Vec3::new(0.3, 0.9, 0.3);
+        let (center, blocked) = move_and_collide(
+            &world,
+            &BlockRegistry::default(),
+            Vec3::new(16.0, 12.0, 16.0),
+            half_extents,
+            Vec3::new(0.0, -5.0, 0.0),
+            true,
+        );
+        assert!(blocked.y);
+        assert!((center.y - (10.0 + half_extents.y)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn unloaded_chunks_stop_movement_only_when_solid() {
+        let world = floor_world(10);
+        let half_extents = Vec3::new(0.3, 0.9, 0.3);
+        let (center, blocked) = move_and_collide(
+            &world,
+            &BlockRegistry::default(),
+            Vec3::new(30.0, 11.0, 16.0),
+            half_extents,
+            Vec3::new(4.0, 0.0, 0.0),
+            true,
+        );
+        assert_eq!(blocked, BVec3::new(true, false, false));
+        assert!((center.x - (CHUNK_SIZE as f32 - half_extents.x)).abs() < 1e-5);
+
+        let (center, blocked) = move_and_collide(
+            &world,
+            &BlockRegistry::default(),
+            Vec3::new(30.0, 11.0, 16.0),
+            half_extents,
+            Vec3::X * 4.0,
+            false,
+        );
+        assert_eq!(blocked, BVec3::FALSE);
+        assert_eq!(center, Vec3::new(34.0, 11.0, 16.0));
+    }
+}
@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::chunk::{BlockId, AIR, DIRT, GRASS, SAND, STONE, WATER};
+use super::coords::Face;
+
+/// Texture of each side of a block, as a texture layer name in [`BlockDefinition`] and as the
+/// resolved layer index in [`BlockRegistry::face_layers`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockFaces<T = String> {
+    pub top: T,
+    pub bottom: T,
+    pub side: T,
+}
+
+impl<T: Clone> BlockFaces<T> {
+    pub fn all(texture: T) -> Self {
+        Self {
+            top: texture.clone(),
+            bottom: texture.clone(),
+            side: texture,
+        }
+    }
+
+    pub fn get(&self, face: Face) -> &T {
+        match face {
+            Face::PosY => &self.top,
+            Face::NegY => &self.bottom,
+            _ => &self.side,
+        }
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> BlockFaces<U> {
+        BlockFaces {
+            top: f(&self.top),
+            bottom: f(&self.bottom),
+            side: f(&self.side),
+        }
+    }
+}
+
+/// One layer of the block texture array
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextureLayer {
+    /// Name block faces refer to the layer by
+    pub name: String,
+    /// Asset path of the image
+    pub path: String,
+}
+
+/// How a block's faces are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderLayer {
+    /// Hides the faces of its neighbours
+    #[default]
+    Opaque,
+    /// Texels with low alpha are discarded, neighbours stay visible through the gaps
+    Cutout,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockDefinition {
+    /// Identifier used in block files
+    pub name: String,
+    /// Name shown to the player
+    pub display_name: String,
+    pub render_layer: RenderLayer,
+    /// Solid blocks stop the player and the colliding camera
+    pub solid: bool,
+    pub faces: BlockFaces,
+    /// Color used for the block in top-down map images
+    pub map_color: [u8; 3],
+}
+
+/// Directory of the block face textures, relative to the asset folder
+pub const BLOCK_TEXTURE_DIR: &str = "textures/blocks";
+
+/// Layers every registry starts with, ahead of the block textures
+pub const BUILTIN_TEXTURES: [(&str, &str); 2] =
+    [("texture", "texture.png"), ("bw-texture", "bw-texture.png")];
+
+/// Block definitions indexed by [`BlockId`], plus the texture layers their faces sample
+#[derive(Resource, Clone, Debug)]
+pub struct BlockRegistry {
+    blocks: Vec<BlockDefinition>,
+    /// Layer indices of the face textures of each block, resolved on registration
+    face_layers: Vec<BlockFaces<u32>>,
+    textures: Vec<TextureLayer>,
+}
+
+impl BlockRegistry {
+    /// A registry with only air and the [`BUILTIN_TEXTURES`]
+    pub fn new() -> Self {
+        let mut registry = Self {
+            blocks: vec![BlockDefinition {
+                name: "air".to_string(),
+                display_name: "Air".to_string(),
+                render_layer: RenderLayer::Cutout,
+                solid: false,
+                faces: BlockFaces::all(String::new()),
+                map_color: [0, 0, 0],
+            }],
+            face_layers: vec![BlockFaces::all(0)],
+            textures: Vec::new(),
+        };
+        for (name, path) in BUILTIN_TEXTURES {
+            registry.add_texture(name, path);
+        }
+        registry
+    }
+
+    /// Appends a layer to the texture array, or points an existing name at a new image.
+    /// Returns the layer index.
+    pub fn add_texture(&mut self, name: impl Into<String>, path: impl Into<String>) -> u32 {
+        let (name, path) = (name.into(), path.into());
+        if let Some(layer) = self.texture_layer(&name) {
+            self.textures[layer as usize].path = path;
+            return layer;
+        }
+        self.textures.push(TextureLayer { name, path });
+        (self.textures.len() - 1) as u32
+    }
+
+    /// Adds `<BLOCK_TEXTURE_DIR>/<name>.png` for every face texture name without a layer yet
+    pub fn add_block_textures(&mut self, faces: &BlockFaces) {
+        for name in [&faces.top, &faces.bottom, &faces.side] {
+            if self.texture_layer(name).is_none() {
+                self.add_texture(name.clone(), format!("{BLOCK_TEXTURE_DIR}/{name}.png"));
+            }
+        }
+    }
+
+    pub fn texture_layer(&self, name: &str) -> Option<u32> {
+        self.textures
+            .iter()
+            .position(|texture| texture.name == name)
+            .map(|layer| layer as u32)
+    }
+
+    /// Texture array layers in order
+    pub fn textures(&self) -> &[TextureLayer] {
+        &self.textures
+    }
+
+    /// Adds a block with the next free id.
+    /// Face textures are looked up by name here, so they have to be added first.
+    /// Unknown names use the first layer.
+    pub fn register(&mut self, definition: BlockDefinition) -> BlockId {
+        let id = self.blocks.len() as BlockId;
+        self.insert(id, definition);
+        id
+    }
+
+    /// Defines the block with the given id, replacing any previous definition. Ids skipped
+    /// over are filled with opaque placeholders, like ids the registry does not know.
+    pub fn insert(&mut self, id: BlockId, definition: BlockDefinition) {
+        let index = id as usize;
+        while self.blocks.len() <= index {
+            let id = self.blocks.len();
+            self.blocks.push(BlockDefinition {
+                name: format!("unknown_{id}"),
+                display_name: format!("Unknown ({id})"),
+                render_layer: RenderLayer::Opaque,
+                solid: true,
+                faces: BlockFaces::all(String::new()),
+                map_color: [255, 0, 255],
+            });
+            self.face_layers.push(BlockFaces::all(0));
+        }
+        self.face_layers[index] = definition
+            .faces
+            .map(|name| self.texture_layer(name).unwrap_or_default());
+        self.blocks[index] = definition;
+    }
+
+    /// Texture array layers of the faces of a block, the first layer for unknown ids
+    pub fn face_layers(&self, id: BlockId) -> BlockFaces<u32> {
+        self.face_layers
+            .get(id as usize)
+            .copied()
+            .unwrap_or(BlockFaces::all(0))
+    }
+
+    pub fn get(&self, id: BlockId) -> Option<&BlockDefinition> {
+        self.blocks.get(id as usize)
+    }
+
+    /// Unknown ids are treated as opaque so that bad data never opens holes in the terrain
+    pub fn is_opaque(&self, id: BlockId) -> bool {
+        id != AIR
+            && self
+                .get(id)
+                .is_none_or(|block| block.render_layer == RenderLayer::Opaque)
+    }
+
+    /// Unknown ids are solid, like they are opaque
+    pub fn is_solid(&self, id: BlockId) -> bool {
+        id != AIR && self.get(id).is_none_or(|block| block.solid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BlockId, &BlockDefinition)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(id, block)| (id as BlockId, block))
+    }
+}
+
+/// The blocks the world generator places. Matches the shipped files in `assets/blocks`, which
+/// replace these when the game runs with [`ResourcePackPlugin`](super::resource_pack::ResourcePackPlugin).
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        let opaque =
+            |name: &str, display_name: &str, faces: BlockFaces<&str>, map_color| BlockDefinition {
+                name: name.to_string(),
+                display_name: display_name.to_string(),
+                render_layer: RenderLayer::Opaque,
+                solid: true,
+                faces: faces.map(|texture| texture.to_string()),
+                map_color,
+            };
+
+        let mut registry = BlockRegistry::new();
+        for (id, definition) in [
+            (
+                STONE,
+                opaque("stone", "Stone", BlockFaces::all("stone"), [125, 125, 125]),
+            ),
+            (
+                DIRT,
+                opaque("dirt", "Dirt", BlockFaces::all("dirt"), [134, 96, 67]),
+            ),
+            (
+                GRASS,
+                opaque(
+                    "grass",
+                    "Grass",
+                    BlockFaces {
+                        top: "grass_top",
+                        bottom: "dirt",
+                        side: "grass_side",
+                    },
+                    [95, 159, 53],
+                ),
+            ),
+            (
+                SAND,
+                opaque("sand", "Sand", BlockFaces::all("sand"), [219, 207, 163]),
+            ),
+            (
+                WATER,
+                BlockDefinition {
+                    render_layer: RenderLayer::Cutout,
+                    solid: false,
+                    ..opaque("water", "Water", BlockFaces::all("water"), [64, 96, 200])
+                },
+            ),
+        ] {
+            registry.add_block_textures(&definition.faces);
+            registry.insert(id, definition);
+        }
+        registry
+    }
+}
@@ -0,0 +1,340 @@
+use super::coords::LocalPos;
+
+/// Chunks are cubes of this many blocks along each axis
+pub const CHUNK_SIZE: usize = 32;
+pub const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Lowest block y of the world
+pub const WORLD_MIN_Y: i32 = -64;
+/// Block y above the highest block of the world
+pub const WORLD_MAX_Y: i32 = 320;
+/// Chunk y of the bottom and top chunk layers of the world
+pub const MIN_CHUNK_Y: i32 = WORLD_MIN_Y.div_euclid(CHUNK_SIZE as i32);
+pub const MAX_CHUNK_Y: i32 = (WORLD_MAX_Y - 1).div_euclid(CHUNK_SIZE as i32);
+
+pub type BlockId = u16;
+
+pub const AIR: BlockId = 0;
+pub const STONE: BlockId = 1;
+pub const DIRT: BlockId = 2;
+pub const GRASS: BlockId = 3;
+pub const SAND: BlockId = 4;
+pub const WATER: BlockId = 5;
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Height of the sections a chunk is stored in
+pub const SECTION_HEIGHT: usize = 16;
+pub const SECTION_COUNT: usize = CHUNK_SIZE / SECTION_HEIGHT;
+pub const SECTION_VOLUME: usize = CHUNK_SIZE * SECTION_HEIGHT * CHUNK_SIZE;
+
+/// Blocks of one section, one horizontal layer at a time. Sections made of a single block store
+/// just that block, others store a palette of their blocks and bit-packed indices into it.
+#[derive(Clone, Debug)]
+pub enum Section {
+    Uniform(BlockId),
+    Paletted(PalettedSection),
+}
+
+/// Palette indices are packed into words with as few bits as the palette needs. Indices never
+/// straddle two words, the leftover high bits of each word stay unused.
+#[derive(Clone, Debug)]
+pub struct PalettedSection {
+    palette: Vec<BlockId>,
+    bits: usize,
+    words: Vec<u64>,
+}
+
+impl PalettedSection {
+    fn filled(block: BlockId) -> Self {
+        Self {
+            palette: vec![block],
+            bits: 1,
+            words: vec![0; Self::word_count(1)],
+        }
+    }
+
+    fn bits_for(palette_len: usize) -> usize {
+        (usize::BITS - palette_len.saturating_sub(1).leading_zeros()).max(1) as usize
+    }
+
+    fn word_count(bits: usize) -> usize {
+        SECTION_VOLUME.div_ceil(u64::BITS as usize / bits)
+    }
+
+    fn index(&self, i: usize) -> usize {
+        let per_word = u64::BITS as usize / self.bits;
+        let shift = (i % per_word) * self.bits;
+        ((self.words[i / per_word] >> shift) & ((1 << self.bits) - 1)) as usize
+    }
+
+    fn set_index(&mut self, i: usize, index: usize) {
+        let per_word = u64::BITS as usize / self.bits;
+        let shift = (i % per_word) * self.bits;
+        let word = &mut self.words[i / per_word];
+        *word = (*word & !(((1 << self.bits) - 1) << shift)) | ((index as u64) << shift);
+    }
+
+    fn get(&self, i: usize) -> BlockId {
+        self.palette[self.index(i)]
+    }
+
+    fn set(&mut self, i: usize, block: BlockId) {
+        let index = match self.palette.iter().position(|&entry| entry == block) {
+            Some(index) => index,
+            None => {
+                if self.palette.len() == 1 << self.bits {
+                    self.repack(self.bits + 1);
+                }
+                self.palette.push(block);
+                self.palette.len() - 1
+            }
+        };
+        self.set_index(i, index);
+    }
+
+    fn repack(&mut self, bits: usize) {
+        let indices: Vec<usize> = (0..SECTION_VOLUME).map(|i| self.index(i)).collect();
+        self.bits = bits;
+        self.words = vec![0; Self::word_count(bits)];
+        for (i, index) in indices.into_iter().enumerate() {
+            self.set_index(i, index);
+        }
+    }
+
+    pub fn palette(&self) -> &[BlockId] {
+        &self.palette
+    }
+
+    /// Bits per block
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+}
+
+impl Section {
+    /// `blocks` holds [`SECTION_VOLUME`] blocks
+    fn from_blocks(blocks: &[BlockId]) -> Self {
+        let first = blocks[0];
+        if blocks.iter().all(|&block| block == first) {
+            return Section::Uniform(first);
+        }
+
+        let mut palette = Vec::new();
+        for &block in blocks {
+            if !palette.contains(&block) {
+                palette.push(block);
+            }
+        }
+        let bits = PalettedSection::bits_for(palette.len());
+        let mut section = PalettedSection {
+            words: vec![0; PalettedSection::word_count(bits)],
+            palette,
+            bits,
+        };
+        for (i, &block) in blocks.iter().enumerate() {
+            let index = section.palette.iter().position(|&entry| entry == block);
+            section.set_index(i, index.unwrap_or_default());
+        }
+        Section::Paletted(section)
+    }
+
+    pub fn get(&self, i: usize) -> BlockId {
+        match self {
+            Section::Uniform(block) => *block,
+            Section::Paletted(section) => section.get(i),
+        }
+    }
+
+    fn set(&mut self, i: usize, block: BlockId) {
+        match self {
+            Section::Uniform(current) if *current == block => {}
+            Section::Uniform(current) => {
+                let mut section = PalettedSection::filled(*current);
+                section.set(i, block);
+                *self = Section::Paletted(section);
+            }
+            Section::Paletted(section) => section.set(i, block),
+        }
+    }
+
+    /// The block filling the whole section, if it is made of a single block
+    pub fn uniform(&self) -> Option<BlockId> {
+        match self {
+            Section::Uniform(block) => Some(*block),
+            Section::Paletted(_) => None,
+        }
+    }
+
+    /// Heap bytes owned by the section
+    fn heap_size(&self) -> usize {
+        match self {
+            Section::Uniform(_) => 0,
+            Section::Paletted(section) => {
+                section.palette.capacity() * size_of::<BlockId>()
+                    + section.words.capacity() * size_of::<u64>()
+            }
+        }
+    }
+}
+
+/// Voxel contents of a single chunk, stored in [`SECTION_COUNT`] sections from the bottom up
+#[derive(Clone)]
+pub struct ChunkData {
+    sections: [Section; SECTION_COUNT],
+}
+
+impl PartialEq for ChunkData {
+    fn eq(&self, other: &Self) -> bool {
+        self.blocks().eq(other.blocks())
+    }
+}
+
+impl Eq for ChunkData {}
+
+impl ChunkData {
+    pub fn empty() -> Self {
+        Self {
+            sections: std::array::from_fn(|_| Section::Uniform(AIR)),
+        }
+    }
+
+    /// Builds a chunk from [`CHUNK_VOLUME`] blocks in [`ChunkData::blocks`] order
+    pub fn from_blocks(blocks: Vec<BlockId>) -> Option<Self> {
+        (blocks.len() == CHUNK_VOLUME).then(|| Self {
+            sections: std::array::from_fn(|section| {
+                Section::from_blocks(&blocks[section * SECTION_VOLUME..][..SECTION_VOLUME])
+            }),
+        })
+    }
+
+    fn index(pos: LocalPos) -> (usize, usize) {
+        let (section, y) = (pos.y() / SECTION_HEIGHT, pos.y() % SECTION_HEIGHT);
+        (section, (y * CHUNK_SIZE + pos.z()) * CHUNK_SIZE + pos.x())
+    }
+
+    pub fn get(&self, pos: LocalPos) -> BlockId {
+        let (section, i) = Self::index(pos);
+        self.sections[section].get(i)
+    }
+
+    pub fn set(&mut self, pos: LocalPos, block: BlockId) {
+        let (section, i) = Self::index(pos);
+        self.sections[section].set(i, block);
+    }
+
+    /// Every block, in [`LocalPos::all`] order
+    pub fn blocks(&self) -> impl Iterator<Item = BlockId> + '_ {
+        self.sections
+            .iter()
+            .flat_map(|section| (0..SECTION_VOLUME).map(move |i| section.get(i)))
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The block filling the whole chunk, if it is made of a single block
+    pub fn uniform(&self) -> Option<BlockId> {
+        let block = self.sections[0].uniform()?;
+        self.sections[1..]
+            .iter()
+            .all(|section| section.uniform() == Some(block))
+            .then_some(block)
+    }
+
+    /// Drops palette entries that are no longer used and turns sections made of a single block
+    /// uniform. Palettes only grow while blocks are set, so call this once a chunk is built.
+    pub fn compact(&mut self) {
+        for section in &mut self.sections {
+            if let Section::Paletted(paletted) = section {
+                let blocks: Vec<BlockId> = (0..SECTION_VOLUME).map(|i| paletted.get(i)).collect();
+                *section = Section::from_blocks(&blocks);
+            }
+        }
+    }
+
+    /// Bytes used by the chunk, including its palettes and packed indices
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Self>() + self.sections.iter().map(Section::heap_size).sum::<usize>()
+    }
+
+    /// FNV-1a hash of the block contents, stable across platforms, runs and Rust versions
+    pub fn content_hash(&self) -> u64 {
+        self.blocks().fold(FNV_OFFSET_BASIS, |hash, block| {
+            fnv1a(hash, &block.to_le_bytes())
+        })
+    }
+
+    /// Height above the chunk's bottom of the highest non-air block in the column through
+    /// `column`, whose y is ignored, `0` if the column is empty
+    pub fn surface_height(&self, column: LocalPos) -> i32 {
+        (0..CHUNK_SIZE)
+            .rev()
+            .find(|&y| self.get(LocalPos::new(column.x(), y, column.z())) != AIR)
+            .map_or(0, |y| y as i32 + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_grow_their_palette_and_compact_back() {
+        let mut chunk = ChunkData::empty();
+        assert!(chunk
+            .sections()
+            .iter()
+            .all(|section| section.uniform() == Some(AIR)));
+
+        // Five blocks need three bits per index
+        for (x, block) in [STONE, DIRT, GRASS, SAND, WATER].into_iter().enumerate() {
+            chunk.set(LocalPos::new(x, 20, 3), block);
+        }
+        let Section::Paletted(section) = &chunk.sections()[1] else {
+            panic!("expected a paletted section");
+        };
+        assert_eq!(section.palette(), [AIR, STONE, DIRT, GRASS, SAND, WATER]);
+        assert_eq!(section.bits(), 3);
+        assert_eq!(chunk.get(LocalPos::new(3, 20, 3)), SAND);
+        assert_eq!(chunk.get(LocalPos::new(5, 20, 3)), AIR);
+        assert_eq!(chunk.sections()[0].uniform(), Some(AIR));
+
+        for pos in LocalPos::all().filter(|pos| pos.y() / SECTION_HEIGHT == 1) {
+            chunk.set(pos, STONE);
+        }
+        let before = chunk.memory_usage();
+        chunk.compact();
+        assert_eq!(chunk.sections()[1].uniform(), Some(STONE));
+        assert!(chunk.memory_usage() < before);
+        assert_eq!(chunk.memory_usage(), size_of::<ChunkData>());
+        assert_eq!(chunk.uniform(), None);
+        assert_eq!(ChunkData::empty().uniform(), Some(AIR));
+    }
+
+    #[test]
+    fn from_blocks_round_trips() {
+        let blocks: Vec<BlockId> = (0..CHUNK_VOLUME)
+            .map(|i| match i / SECTION_VOLUME {
+                0 => STONE,
+                _ => (i % 7) as BlockId,
+            })
+            .collect();
+        let chunk = ChunkData::from_blocks(blocks.clone()).unwrap();
+        assert!(chunk.blocks().eq(blocks.iter().copied()));
+        assert!(LocalPos::all()
+            .map(|pos| chunk.get(pos))
+            .eq(blocks.iter().copied()));
+        assert_eq!(chunk.sections()[0].uniform(), Some(STONE));
+        assert_eq!(chunk.sections()[1].uniform(), None);
+        assert!(ChunkData::from_blocks(vec![AIR; 3]).is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::chunk::{BlockId, DIRT, GRASS, SAND, STONE};
+use super::noise_graph::{NoiseLayer, NoiseNode};
+use super::ron_asset::RonAssetLoader;
+use super::world_generator::{Biome, WorldSettings};
+
+/// Blocks and map color of one biome
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BiomeConfig {
+    /// Top block of the column
+    pub surface: BlockId,
+    /// Blocks under the surface, stone below them
+    pub subsurface: BlockId,
+    pub subsurface_depth: usize,
+    /// Color used for the biome in top-down map images
+    pub map_color: [u8; 3],
+}
+
+/// Which biome a column gets and what each biome is made of
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BiomeTable {
+    /// Normalized height, 0 to 1, above which columns are mountains
+    pub mountain_height: f32,
+    /// Moisture noise below which the remaining columns are deserts, plains otherwise
+    pub desert_moisture: f32,
+    pub plains: BiomeConfig,
+    pub desert: BiomeConfig,
+    pub mountains: BiomeConfig,
+}
+
+impl Default for BiomeTable {
+    fn default() -> Self {
+        Self {
+            mountain_height: 0.75,
+            desert_moisture: -0.3,
+            plains: BiomeConfig {
+                surface: GRASS,
+                subsurface: DIRT,
+                subsurface_depth: 3,
+                map_color: [95, 159, 53],
+            },
+            desert: BiomeConfig {
+                surface: SAND,
+                subsurface: SAND,
+                subsurface_depth: 3,
+                map_color: [219, 207, 163],
+            },
+            mountains: BiomeConfig {
+                surface: STONE,
+                subsurface: STONE,
+                subsurface_depth: 3,
+                map_color: [140, 140, 150],
+            },
+        }
+    }
+}
+
+impl BiomeTable {
+    pub fn get(&self, biome: Biome) -> &BiomeConfig {
+        match biome {
+            Biome::Plains => &self.plains,
+            Biome::Desert => &self.desert,
+            Biome::Mountains => &self.mountains,
+        }
+    }
+}
+
+/// Terrain shape and biomes, loaded from `assets/worldgen` by [`WorldGenConfigPlugin`].
+/// Fields missing from a file keep their default, so presets only list what they change.
+#[derive(Asset, TypePath, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationConfig {
+    /// Terrain height in -1..1, from the lowest to the highest terrain
+    pub height: NoiseNode,
+    pub moisture: NoiseLayer,
+    /// Block y of the lowest terrain
+    pub base_height: i32,
+    /// Terrain height added by the height noise at its maximum
+    pub height_variation: f32,
+    /// Columns lower than this are filled with water up to it, at or below `base_height` for a
+    /// dry world
+    pub sea_level: i32,
+    pub biomes: BiomeTable,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            height: NoiseNode::Noise(NoiseLayer {
+                seed_offset: 0,
+                frequency: 0.035,
+                octaves: 5,
+                weighted_strength: 0.5,
+                ..default()
+            }),
+            moisture: NoiseLayer {
+                seed_offset: 1,
+                frequency: 0.00875,
+                octaves: 3,
+                ..default()
+            },
+            base_height: 8,
+            height_variation: 12.0,
+            sea_level: 0,
+            biomes: BiomeTable::default(),
+        }
+    }
+}
+
+impl GenerationConfig {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// Directory of the preset configs, relative to the asset folder
+pub const PRESETS_DIR: &str = "worldgen/presets";
+
+/// Terrain styles shipped as configs in [`PRESETS_DIR`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerrainPreset {
+    /// Warped ridged mountain ranges with snowless stone peaks
+    Alpine,
+    /// Terraced sand plateaus cut by ping-pong noise
+    Mesa,
+    /// Warped islands in a shallow sea
+    Archipelago,
+    /// Level plains at a fixed height
+    Flat,
+    /// Ridged terrain stretched over most of the world height
+    Amplified,
+}
+
+impl TerrainPreset {
+    pub const ALL: [TerrainPreset; 5] = [
+        TerrainPreset::Alpine,
+        TerrainPreset::Mesa,
+        TerrainPreset::Archipelago,
+        TerrainPreset::Flat,
+        TerrainPreset::Amplified,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TerrainPreset::Alpine => "alpine",
+            TerrainPreset::Mesa => "mesa",
+            TerrainPreset::Archipelago => "archipelago",
+            TerrainPreset::Flat => "flat",
+            TerrainPreset::Amplified => "amplified",
+        }
+    }
+
+    /// Asset path of the preset's config
+    pub fn path(self) -> String {
+        format!("{PRESETS_DIR}/{}.ron", self.name())
+    }
+}
+
+impl FromStr for TerrainPreset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        TerrainPreset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = TerrainPreset::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown preset `{name}`, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Keeps [`WorldSettings::generation`] in sync with a config file. With the `dev` feature's
+/// file watcher, saving the file regenerates the loaded chunks in place.
+pub struct WorldGenConfigPlugin {
+    /// Asset path of the config
+    pub path: String,
+}
+
+impl WorldGenConfigPlugin {
+    pub fn preset(preset: TerrainPreset) -> Self {
+        Self {
+            path: preset.path(),
+        }
+    }
+}
+
+impl Default for WorldGenConfigPlugin {
+    fn default() -> Self {
+        Self {
+            path: "worldgen/default.ron".to_string(),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct WorldGenConfigHandle(Handle<GenerationConfig>);
+
+impl Plugin for WorldGenConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let path = self.path.clone();
+        app.init_asset::<GenerationConfig>()
+            .register_asset_loader(RonAssetLoader::<GenerationConfig>::new(&["worldgen.ron"]))
+            .add_systems(
+                Startup,
+                move |mut commands: Commands, asset_server: Res<AssetServer>| {
+                    let handle = asset_server.load(path.clone());
+                    commands.insert_resource(WorldGenConfigHandle(handle));
+                },
+            )
+            .add_systems(Update, apply_generation_config);
+    }
+}
+
+fn apply_generation_config(
+    mut events: EventReader<AssetEvent<GenerationConfig>>,
+    handle: Res<WorldGenConfigHandle>,
+    configs: Res<Assets<GenerationConfig>>,
+    mut settings: ResMut<WorldSettings>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+        let Some(config) = configs.get(&handle.0) else {
+            continue;
+        };
+        if settings.generation != *config {
+            info!("Applying world generation config");
+            settings.generation = config.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::world_generator::world_generator::TerrainSampler;
+
+    fn asset_path(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join(path)
+    }
+
+    #[test]
+    fn shipped_config_matches_defaults() {
+        assert_eq!(
+            GenerationConfig::load(asset_path("worldgen/default.ron")).unwrap(),
+            GenerationConfig::default()
+        );
+    }
+
+    #[test]
+    fn presets_load_and_shape_distinct_terrain() {
+        let heights = |generation: GenerationConfig| {
+            let sampler = TerrainSampler::new(&WorldSettings {
+                generation,
+                ..default()
+            });
+            (-64..64)
+                .step_by(8)
+                .flat_map(|x| (-64..64).step_by(8).map(move |z| (x, z)))
+                .map(|(x, z)| sampler.sample(x, z).height)
+                .collect::<Vec<_>>()
+        };
+
+        let mut seen = vec![heights(GenerationConfig::default())];
+        for preset in TerrainPreset::ALL {
+            assert_eq!(preset.name().parse(), Ok(preset));
+            let config = GenerationConfig::load(asset_path(&preset.path()))
+                .unwrap_or_else(|error| panic!("{}: {error}", preset.name()));
+            let preset_heights = heights(config);
+            assert!(
+                !seen.contains(&preset_heights),
+                "{} looks like another preset",
+                preset.name()
+            );
+            seen.push(preset_heights);
+        }
+
+        let flat = &seen[1 + TerrainPreset::Flat as usize];
+        assert!(flat.iter().all(|&height| height == flat[0]));
+        assert!("cliffs".parse::<TerrainPreset>().is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use super::chunk::CHUNK_SIZE;
+
+const CHUNK_EXTENT: IVec3 = IVec3::splat(CHUNK_SIZE as i32);
+
+/// Direction a block or chunk face points in. The order is the one the mesher packs normals in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Face {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [
+        Face::PosX,
+        Face::NegX,
+        Face::PosY,
+        Face::NegY,
+        Face::PosZ,
+        Face::NegZ,
+    ];
+
+    /// Outward unit normal
+    pub const fn normal(self) -> IVec3 {
+        match self {
+            Face::PosX => IVec3::X,
+            Face::NegX => IVec3::NEG_X,
+            Face::PosY => IVec3::Y,
+            Face::NegY => IVec3::NEG_Y,
+            Face::PosZ => IVec3::Z,
+            Face::NegZ => IVec3::NEG_Z,
+        }
+    }
+
+    /// The face with a unit normal, `None` for anything else
+    pub fn from_normal(normal: IVec3) -> Option<Self> {
+        Face::ALL.into_iter().find(|face| face.normal() == normal)
+    }
+
+    pub const fn opposite(self) -> Self {
+        match self {
+            Face::PosX => Face::NegX,
+            Face::NegX => Face::PosX,
+            Face::PosY => Face::NegY,
+            Face::NegY => Face::PosY,
+            Face::PosZ => Face::NegZ,
+            Face::NegZ => Face::PosZ,
+        }
+    }
+
+    /// Index of the axis the face is perpendicular to, 0 for x through 2 for z
+    pub const fn axis(self) -> usize {
+        self as usize / 2
+    }
+}
+
+/// Position of a block in the world
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BlockPos(pub IVec3);
+
+impl BlockPos {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self(IVec3::new(x, y, z))
+    }
+
+    /// Block containing a world-space point
+    pub fn containing(point: Vec3) -> Self {
+        Self(point.floor().as_ivec3())
+    }
+
+    /// Chunk holding the block, rounding towards negative infinity
+    pub fn chunk(self) -> ChunkPos {
+        ChunkPos(self.0.div_euclid(CHUNK_EXTENT))
+    }
+
+    /// Position of the block within its [`chunk`](Self::chunk)
+    pub fn local(self) -> LocalPos {
+        LocalPos(self.0.rem_euclid(CHUNK_EXTENT).as_uvec3())
+    }
+
+    /// World-space position of the block's minimum corner
+    pub fn as_vec3(self) -> Vec3 {
+        self.0.as_vec3()
+    }
+
+    /// World-space position of the block's center
+    pub fn center(self) -> Vec3 {
+        self.as_vec3() + 0.5
+    }
+
+    /// The block sharing `face` with this one
+    pub fn offset(self, face: Face) -> Self {
+        Self(self.0 + face.normal())
+    }
+
+    /// The six blocks sharing a face with this one, in [`Face::ALL`] order
+    pub fn neighbours(self) -> impl Iterator<Item = Self> {
+        Face::ALL.into_iter().map(move |face| self.offset(face))
+    }
+}
+
+impl From<IVec3> for BlockPos {
+    fn from(pos: IVec3) -> Self {
+        Self(pos)
+    }
+}
+
+impl From<BlockPos> for IVec3 {
+    fn from(pos: BlockPos) -> Self {
+        pos.0
+    }
+}
+
+impl fmt::Display for BlockPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.0.x, self.0.y, self.0.z)
+    }
+}
+
+/// Position of a chunk, in chunks from the origin. Orders by x, then y, then z.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ChunkPos(pub IVec3);
+
+impl ChunkPos {
+    pub const ZERO: Self = Self(IVec3::ZERO);
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self(IVec3::new(x, y, z))
+    }
+
+    /// Chunk containing a world-space point
+    pub fn containing(point: Vec3) -> Self {
+        BlockPos::containing(point).chunk()
+    }
+
+    /// The chunk's block with the lowest coordinates
+    pub fn origin(self) -> BlockPos {
+        BlockPos(self.0 * CHUNK_EXTENT)
+    }
+
+    /// World position of a block in this chunk
+    pub fn block(self, local: LocalPos) -> BlockPos {
+        BlockPos(self.origin().0 + local.as_ivec3())
+    }
+
+    /// The chunk sharing `face` with this one
+    pub fn offset(self, face: Face) -> Self {
+        Self(self.0 + face.normal())
+    }
+
+    /// The six chunks sharing a face with this one, in [`Face::ALL`] order
+    pub fn neighbours(self) -> impl Iterator<Item = Self> {
+        Face::ALL.into_iter().map(move |face| self.offset(face))
+    }
+}
+
+impl PartialOrd for ChunkPos {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ChunkPos {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.to_array().cmp(&other.0.to_array())
+    }
+}
+
+impl From<IVec3> for ChunkPos {
+    fn from(pos: IVec3) -> Self {
+        Self(pos)
+    }
+}
+
+impl From<ChunkPos> for IVec3 {
+    fn from(pos: ChunkPos) -> Self {
+        pos.0
+    }
+}
+
+impl fmt::Display for ChunkPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.0.x, self.0.y, self.0.z)
+    }
+}
+
+/// Position of a block within its chunk, every axis in `0..CHUNK_SIZE`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LocalPos(UVec3);
+
+impl LocalPos {
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        assert!(
+            x < CHUNK_SIZE && y < CHUNK_SIZE && z < CHUNK_SIZE,
+            "{x} {y} {z} is outside the chunk"
+        );
+        Self(UVec3::new(x as u32, y as u32, z as u32))
+    }
+
+    /// Every position in the chunk, one horizontal layer at a time from the bottom up, the
+    /// order [`ChunkData::blocks`](super::chunk::ChunkData::blocks) stores them in
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..CHUNK_SIZE).flat_map(|y| {
+            (0..CHUNK_SIZE).flat_map(move |z| (0..CHUNK_SIZE).map(move |x| Self::new(x, y, z)))
+        })
+    }
+
+    pub fn x(self) -> usize {
+        self.0.x as usize
+    }
+
+    pub fn y(self) -> usize {
+        self.0.y as usize
+    }
+
+    pub fn z(self) -> usize {
+        self.0.z as usize
+    }
+
+    pub fn as_ivec3(self) -> IVec3 {
+        self.0.as_ivec3()
+    }
+
+    /// Position of the block's minimum corner relative to the chunk origin
+    pub fn as_vec3(self) -> Vec3 {
+        self.0.as_vec3()
+    }
+}
+
+impl TryFrom<IVec3> for LocalPos {
+    type Error = IVec3;
+
+    /// Fails with the position itself when it lies outside the chunk
+    fn try_from(pos: IVec3) -> Result<Self, IVec3> {
+        if pos.cmpge(IVec3::ZERO).all() && pos.cmplt(CHUNK_EXTENT).all() {
+            Ok(Self(pos.as_uvec3()))
+        } else {
+            Err(pos)
+        }
+    }
+}
+
+impl From<LocalPos> for IVec3 {
+    fn from(pos: LocalPos) -> Self {
+        pos.as_ivec3()
+    }
+}
+
+impl fmt::Display for LocalPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.0.x, self.0.y, self.0.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_blocks_round_down_into_their_chunk() {
+        let size = CHUNK_SIZE as i32;
+        let pos = BlockPos::new(-1, -size, -size - 1);
+        assert_eq!(pos.chunk(), ChunkPos::new(-1, -1, -2));
+        assert_eq!(
+            pos.local(),
+            LocalPos::new(CHUNK_SIZE - 1, 0, CHUNK_SIZE - 1)
+        );
+        assert_eq!(pos.chunk().block(pos.local()), pos);
+
+        assert_eq!(
+            BlockPos::containing(Vec3::new(-0.5, 0.0, 31.9)),
+            BlockPos::new(-1, 0, 31)
+        );
+        assert_eq!(
+            ChunkPos::containing(Vec3::new(-0.5, 32.0, 31.9)),
+            ChunkPos::new(-1, 1, 0)
+        );
+        assert_eq!(
+            ChunkPos::new(-1, 1, 0).origin(),
+            BlockPos::new(-size, size, 0)
+        );
+    }
+
+    #[test]
+    fn local_positions_cover_the_chunk_in_storage_order() {
+        let all: Vec<LocalPos> = LocalPos::all().collect();
+        assert_eq!(all.len(), CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+        assert_eq!(all[1], LocalPos::new(1, 0, 0));
+        assert_eq!(all[CHUNK_SIZE], LocalPos::new(0, 0, 1));
+
+        assert_eq!(
+            LocalPos::try_from(IVec3::new(3, 31, 0)),
+            Ok(LocalPos::new(3, 31, 0))
+        );
+        assert!(LocalPos::try_from(IVec3::new(3, 32, 0)).is_err());
+        assert!(LocalPos::try_from(IVec3::new(-1, 0, 0)).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the chunk")]
+    fn local_positions_outside_the_chunk_panic() {
+        LocalPos::new(0, CHUNK_SIZE, 0);
+    }
+
+    #[test]
+    fn faces_and_neighbours_agree() {
+        for face in Face::ALL {
+            assert_eq!(Face::from_normal(face.normal()), Some(face));
+            assert_eq!(face.opposite().normal(), -face.normal());
+            assert_eq!(face.normal()[face.axis()].abs(), 1);
+        }
+        assert_eq!(Face::from_normal(IVec3::ONE), None);
+
+        let pos = ChunkPos::new(2, -1, 0);
+        let neighbours: Vec<ChunkPos> = pos.neighbours().collect();
+        assert_eq!(neighbours.len(), 6);
+        assert!(neighbours.contains(&ChunkPos::new(2, -2, 0)));
+        assert!(neighbours
+            .iter()
+            .all(|&neighbour| (neighbour.0 - pos.0).abs().element_sum() == 1));
+        assert_eq!(BlockPos::default().neighbours().count(), 6);
+    }
+}
@@ -0,0 +1,488 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use super::block::BlockRegistry;
+use super::coords::ChunkPos;
+use super::mesher::{mesh_chunk, ExpandedMesh};
+use super::textures::{load_image, texture_strip};
+use super::world_generator::VoxelWorld;
+use crate::input_map::{Action, ActionInput};
+
+pub const EXPORT_DIR: &str = "exports";
+const MATERIAL_NAME: &str = "terrain";
+
+/// Writes the loaded terrain to `exports/` as OBJ and glTF on [`Action::ExportTerrain`]
+pub struct TerrainExportPlugin;
+
+impl Plugin for TerrainExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, export_on_key);
+    }
+}
+
+fn export_on_key(actions: ActionInput, world: Res<VoxelWorld>, registry: Res<BlockRegistry>) {
+    if !actions.just_pressed(Action::ExportTerrain) {
+        return;
+    }
+
+    let stem = Path::new(EXPORT_DIR).join("terrain");
+    match export_terrain(&stem, &world, &registry, Path::new("assets")) {
+        Ok(export) => {
+            if let Some(error) = &export.texture_error {
+                warn!("Exported terrain without a texture: {error}");
+            }
+            info!("Exported terrain to {}", export.files.join(", "));
+        }
+        Err(error) => error!("Failed to export terrain: {error}"),
+    }
+}
+
+/// Files written by [`export_terrain`]
+#[derive(Debug)]
+pub struct TerrainExport {
+    pub files: Vec<String>,
+    /// Why the texture was left out, `None` when it was written
+    pub texture_error: Option<io::Error>,
+}
+
+/// Meshes every chunk with its neighbours, sorted by position so exports are reproducible
+pub fn mesh_world(world: &VoxelWorld, registry: &BlockRegistry) -> Vec<(ChunkPos, ExpandedMesh)> {
+    let mut positions: Vec<ChunkPos> = world.chunks.keys().copied().collect();
+    positions.sort();
+    positions
+        .into_iter()
+        .map(|pos| {
+            let mesh = mesh_chunk(&world.chunks[&pos], &world.neighbours(pos), registry);
+            (pos, mesh.expand(registry))
+        })
+        .collect()
+}
+
+/// Writes `<stem>.obj`, `<stem>.gltf` and their companion files, plus `<stem>_textures.png`
+/// with the block textures from `asset_dir` side by side for the materials to sample.
+/// If a layer cannot be read the models are written untextured and the export reports why.
+pub fn export_terrain(
+    stem: &Path,
+    world: &VoxelWorld,
+    registry: &BlockRegistry,
+    asset_dir: &Path,
+) -> io::Result<TerrainExport> {
+    let out_dir = stem.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(out_dir)?;
+
+    let texture = stem.with_file_name(format!("{}_textures.png", file_name(stem)));
+    let texture_error = write_texture_strip(&texture, registry, asset_dir).err();
+    let texture_name = texture_error.is_none().then(|| file_name(&texture));
+
+    let meshes = mesh_world(world, registry);
+    let obj = stem.with_extension("obj");
+    let gltf = stem.with_extension("gltf");
+    write_obj(&obj, &meshes, texture_name.as_deref())?;
+    write_gltf(&gltf, &meshes, texture_name.as_deref())?;
+
+    let mut files = vec![obj.display().to_string(), gltf.display().to_string()];
+    if texture_name.is_some() {
+        files.push(texture.display().to_string());
+    }
+    Ok(TerrainExport {
+        files,
+        texture_error,
+    })
+}
+
+fn write_texture_strip(path: &Path, registry: &BlockRegistry, asset_dir: &Path) -> io::Result<()> {
+    let layers = registry
+        .textures()
+        .iter()
+        .map(|layer| {
+            let path = asset_dir.join(&layer.path);
+            load_image(&path).map_err(|error| {
+                io::Error::new(error.kind(), format!("{}: {error}", path.display()))
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let layers: Vec<&Image> = layers.iter().collect();
+    let strip = texture_strip(&layers)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no block textures"))?;
+    strip
+        .try_into_dynamic()
+        .map_err(io::Error::other)?
+        .save(path)
+        .map_err(io::Error::other)
+}
+
+/// Writes a Wavefront OBJ with one object per chunk and an `.mtl` file, referencing `texture`
+/// if there is one
+pub fn write_obj(
+    path: &Path,
+    meshes: &[(ChunkPos, ExpandedMesh)],
+    texture: Option<&str>,
+) -> io::Result<()> {
+    let mtl = path.with_extension("mtl");
+    let mut out = BufWriter::new(File::create(&mtl)?);
+    writeln!(out, "newmtl {MATERIAL_NAME}")?;
+    writeln!(out, "Kd 1.0 1.0 1.0")?;
+    if let Some(texture) = texture {
+        writeln!(out, "map_Kd {texture}")?;
+    }
+    out.flush()?;
+
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "mtllib {}", file_name(&mtl))?;
+
+    // OBJ indices are global and 1-based
+    let mut offset = 1;
+    for (pos, mesh) in meshes {
+        if mesh.is_empty() {
+            continue;
+        }
+        let origin = pos.origin().as_vec3();
+
+        writeln!(out, "o chunk_{}_{}_{}", pos.0.x, pos.0.y, pos.0.z)?;
+        writeln!(out, "usemtl {MATERIAL_NAME}")?;
+        for p in &mesh.positions {
+            let p = origin + Vec3::from(*p);
+            writeln!(out, "v {} {} {}", p.x, p.y, p.z)?;
+        }
+        for uv in &mesh.uvs {
+            // OBJ texture coordinates start at the bottom of the image
+            writeln!(out, "vt {} {}", uv[0], 1.0 - uv[1])?;
+        }
+        for n in &mesh.normals {
+            writeln!(out, "vn {} {} {}", n[0], n[1], n[2])?;
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] + offset);
+            writeln!(out, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+        offset += mesh.positions.len() as u32;
+    }
+
+    out.flush()
+}
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Writes a glTF 2.0 scene with one node and mesh per chunk. Vertex data goes to a `.bin`
+/// buffer next to the `.gltf` file, which is left out when there are no faces. The single
+/// material samples `texture` if there is one.
+pub fn write_gltf(
+    path: &Path,
+    meshes: &[(ChunkPos, ExpandedMesh)],
+    texture: Option<&str>,
+) -> io::Result<()> {
+    let bin_path = path.with_extension("bin");
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut gltf_meshes = Vec::new();
+    let mut nodes = Vec::new();
+
+    let mut push_view = |buffer: &mut Vec<u8>, bytes: Vec<u8>, target: u32| {
+        let view = format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{target}}}"#,
+            buffer.len(),
+            bytes.len()
+        );
+        buffer.extend(bytes);
+        buffer_views.push(view);
+        buffer_views.len() - 1
+    };
+
+    for (pos, mesh) in meshes.iter().filter(|(_, mesh)| !mesh.is_empty()) {
+        let count = mesh.positions.len();
+        let (min, max) = mesh.positions.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), p| (min.min(Vec3::from(*p)), max.max(Vec3::from(*p))),
+        );
+
+        let view = push_view(
+            &mut buffer,
+            f32_bytes(mesh.positions.as_flattened()),
+            ARRAY_BUFFER,
+        );
+        accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{FLOAT},"count":{count},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            min.x, min.y, min.z, max.x, max.y, max.z
+        ));
+        let view = push_view(
+            &mut buffer,
+            f32_bytes(mesh.normals.as_flattened()),
+            ARRAY_BUFFER,
+        );
+        accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{FLOAT},"count":{count},"type":"VEC3"}}"#
+        ));
+        let view = push_view(
+            &mut buffer,
+            f32_bytes(mesh.uvs.as_flattened()),
+            ARRAY_BUFFER,
+        );
+        accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{FLOAT},"count":{count},"type":"VEC2"}}"#
+        ));
+        let indices: Vec<u8> = mesh.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = push_view(&mut buffer, indices, ELEMENT_ARRAY_BUFFER);
+        accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{UNSIGNED_INT},"count":{},"type":"SCALAR"}}"#,
+            mesh.indices.len()
+        ));
+
+        let first = accessors.len() - 4;
+        gltf_meshes.push(format!(
+            r#"{{"name":"chunk_{x}_{y}_{z}","primitives":[{{"attributes":{{"POSITION":{},"NORMAL":{},"TEXCOORD_0":{}}},"indices":{},"material":0}}]}}"#,
+            first,
+            first + 1,
+            first + 2,
+            first + 3,
+            x = pos.0.x,
+            y = pos.0.y,
+            z = pos.0.z,
+        ));
+        let origin = pos.origin().as_vec3();
+        nodes.push(format!(
+            r#"{{"name":"chunk_{}_{}_{}","mesh":{},"translation":[{},{},{}]}}"#,
+            pos.0.x,
+            pos.0.y,
+            pos.0.z,
+            gltf_meshes.len() - 1,
+            origin.x,
+            origin.y,
+            origin.z
+        ));
+    }
+
+    // An empty buffer is invalid glTF, so a world without faces gets no vertex data at all
+    let data = if buffer.is_empty() {
+        String::new()
+    } else {
+        fs::write(&bin_path, &buffer)?;
+        format!(
+            r#",
+  "buffers": [{{"uri": "{}", "byteLength": {}}}],
+  "bufferViews": [{}],
+  "accessors": [{}]"#,
+            file_name(&bin_path),
+            buffer.len(),
+            buffer_views.join(","),
+            accessors.join(","),
+        )
+    };
+
+    let scene_nodes: Vec<String> = (0..nodes.len()).map(|i| i.to_string()).collect();
+    let (base_color, textures) = match texture {
+        Some(texture) => (
+            r#""baseColorTexture": {"index": 0}, "#.to_string(),
+            format!(
+                r#"
+  "textures": [{{"sampler": 0, "source": 0}}],
+  "samplers": [{{"magFilter": 9728, "minFilter": 9728}}],
+  "images": [{{"uri": "{texture}"}}],"#
+            ),
+        ),
+        None => (String::new(), String::new()),
+    };
+    let json = format!(
+        r#"{{
+  "asset": {{"version": "2.0", "generator": "bevy_minecraft_clone"}},
+  "scene": 0,
+  "scenes": [{{"nodes": [{}]}}],
+  "nodes": [{}],
+  "meshes": [{}],
+  "materials": [{{"name": "{MATERIAL_NAME}", "pbrMetallicRoughness": {{{base_color}"metallicFactor": 0.0, "roughnessFactor": 1.0}}}}],{textures}{data}
+}}
+"#,
+        scene_nodes.join(","),
+        nodes.join(","),
+        gltf_meshes.join(","),
+    );
+    fs::write(path, json)
+}
+
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::world_generator::chunk::{ChunkData, CHUNK_SIZE, STONE};
+    use crate::world_generator::coords::LocalPos;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("export-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// One floating block in each of two chunks side by side, 6 faces and 24 vertices each
+    fn two_blocks() -> VoxelWorld {
+        let mut world = VoxelWorld::default();
+        for pos in [ChunkPos::new(0, 0, 0), ChunkPos::new(1, 0, 0)] {
+            let mut chunk = ChunkData::empty();
+            chunk.set(LocalPos::new(4, 4, 4), STONE);
+            world.chunks.insert(pos, chunk);
+        }
+        world
+    }
+
+    fn lines<'a>(text: &'a str, prefix: &'a str) -> impl Iterator<Item = &'a str> {
+        text.lines()
+            .filter_map(move |line| line.strip_prefix(prefix))
+    }
+
+    /// The unsigned integer after every occurrence of `"key":` in a glTF file
+    fn json_numbers(json: &str, key: &str) -> Vec<usize> {
+        json.split(&format!("\"{key}\":"))
+            .skip(1)
+            .map(|rest| {
+                let digits: String = rest
+                    .trim_start()
+                    .chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect();
+                digits.parse().unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn obj_indices_continue_across_chunks() {
+        let dir = temp_dir("obj");
+        let path = dir.join("terrain.obj");
+        let meshes = mesh_world(&two_blocks(), &BlockRegistry::default());
+        write_obj(&path, &meshes, Some("terrain_textures.png")).unwrap();
+        let obj = fs::read_to_string(&path).unwrap();
+        let mtl = fs::read_to_string(dir.join("terrain.mtl")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            lines(&obj, "o ").collect::<Vec<_>>(),
+            ["chunk_0_0_0", "chunk_1_0_0"]
+        );
+        assert_eq!(lines(&obj, "v ").count(), 48);
+        assert_eq!(lines(&obj, "vt ").count(), 48);
+        assert_eq!(lines(&obj, "vn ").count(), 48);
+        assert!(mtl.contains("map_Kd terrain_textures.png"));
+
+        let faces: Vec<Vec<u32>> = lines(&obj, "f ")
+            .map(|face| {
+                face.split([' ', '/'])
+                    .map(|index| index.parse().unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(faces.len(), 24);
+        let (first, second) = faces.split_at(12);
+        assert!(first.iter().flatten().all(|&i| (1..=24).contains(&i)));
+        assert!(second.iter().flatten().all(|&i| (25..=48).contains(&i)));
+        assert_eq!(second.iter().flatten().min(), Some(&25));
+
+        // The second block sits one chunk further along x
+        let x: Vec<f32> = lines(&obj, "v ")
+            .map(|v| v.split(' ').next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(x[..24].iter().copied().fold(f32::MAX, f32::min), 4.0);
+        assert_eq!(
+            x[24..].iter().copied().fold(f32::MAX, f32::min),
+            CHUNK_SIZE as f32 + 4.0
+        );
+    }
+
+    #[test]
+    fn gltf_buffer_and_accessors_match_the_meshes() {
+        let dir = temp_dir("gltf");
+        let path = dir.join("terrain.gltf");
+        let meshes = mesh_world(&two_blocks(), &BlockRegistry::default());
+        write_gltf(&path, &meshes, Some("terrain_textures.png")).unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+        let bin = fs::read(dir.join("terrain.bin")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(json.contains(r#""uri": "terrain.bin""#));
+        assert!(json.contains(r#""images": [{"uri": "terrain_textures.png"}]"#));
+
+        // Position, normal, uv and index accessors for each chunk
+        assert_eq!(
+            json_numbers(&json, "count"),
+            [24, 24, 24, 36, 24, 24, 24, 36]
+        );
+        // The buffer comes before its views
+        let lengths = json_numbers(&json, "byteLength");
+        let (buffer, views) = lengths.split_first().unwrap();
+        assert_eq!(*buffer, bin.len());
+        assert_eq!(
+            views,
+            [
+                24 * 12,
+                24 * 12,
+                24 * 8,
+                36 * 4,
+                24 * 12,
+                24 * 12,
+                24 * 8,
+                36 * 4
+            ]
+        );
+        assert_eq!(views.iter().sum::<usize>(), *buffer);
+    }
+
+    #[test]
+    fn empty_worlds_export_without_a_buffer() {
+        let dir = temp_dir("empty");
+        let path = dir.join("terrain.gltf");
+        let mut world = VoxelWorld::default();
+        world.chunks.insert(ChunkPos::ZERO, ChunkData::empty());
+        let meshes = mesh_world(&world, &BlockRegistry::default());
+        write_gltf(&path, &meshes, None).unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+        let bin_written = dir.join("terrain.bin").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!bin_written);
+        assert!(json.contains(r#""nodes": []"#) && json.contains(r#""meshes": []"#));
+        assert!(!json.contains("buffer") && !json.contains("accessors"));
+    }
+
+    #[test]
+    fn missing_textures_are_left_out_of_the_models() {
+        let dir = temp_dir("untextured");
+        let stem = dir.join("terrain");
+        let export = export_terrain(
+            &stem,
+            &two_blocks(),
+            &BlockRegistry::default(),
+            &dir.join("no_assets"),
+        )
+        .unwrap();
+        let mtl = fs::read_to_string(dir.join("terrain.mtl")).unwrap();
+        let json = fs::read_to_string(dir.join("terrain.gltf")).unwrap();
+        let texture_written = dir.join("terrain_textures.png").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            export.texture_error.map(|error| error.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+        assert_eq!(export.files.len(), 2);
+        assert!(!texture_written);
+        assert!(!mtl.contains("map_Kd"));
+        assert!(!json.contains("images") && !json.contains("baseColorTexture"));
+    }
+}
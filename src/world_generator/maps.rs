@@ -0,0 +1,318 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy::render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use super::block::BlockRegistry;
+use super::noise_graph::NoiseGraph;
+use super::world_generator::{ColumnSample, TerrainSampler};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapKind {
+    Height,
+    Biome,
+    Surface,
+}
+
+impl MapKind {
+    pub const ALL: [MapKind; 3] = [MapKind::Height, MapKind::Biome, MapKind::Surface];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MapKind::Height => "height",
+            MapKind::Biome => "biome",
+            MapKind::Surface => "surface",
+        }
+    }
+}
+
+/// Largest width or height of a rendered map, in pixels
+pub const MAX_MAP_SIZE: u32 = 16384;
+
+/// Area of the world to render, in block coordinates on the xz plane.
+/// Every pixel covers `scale` by `scale` blocks and samples the column at its minimum corner.
+#[derive(Clone, Copy, Debug)]
+pub struct MapArea {
+    pub min: IVec2,
+    pub size: UVec2,
+    pub scale: u32,
+}
+
+impl MapArea {
+    /// Size of the rendered image in pixels
+    pub fn pixels(&self) -> UVec2 {
+        self.size / self.scale.max(1)
+    }
+
+    /// Fails when the image would have no pixels or be larger than [`MAX_MAP_SIZE`]
+    pub fn validate(&self) -> io::Result<()> {
+        let pixels = self.pixels();
+        if pixels.min_element() == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "an area of {}x{} blocks is smaller than one pixel at scale {}",
+                    self.size.x,
+                    self.size.y,
+                    self.scale.max(1)
+                ),
+            ));
+        }
+        if pixels.max_element() > MAX_MAP_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "a map of {}x{} pixels is larger than {MAX_MAP_SIZE} on a side",
+                    pixels.x, pixels.y
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for MapArea {
+    fn default() -> Self {
+        Self {
+            min: IVec2::splat(-256),
+            size: UVec2::splat(512),
+            scale: 1,
+        }
+    }
+}
+
+/// Renders a top-down map straight from the generator's column samples.
+/// No chunks are generated and nothing touches the GPU, the image only lives in the main world.
+/// Heights are shaded from black at the lowest column in the area to white at the highest,
+/// which takes a first pass over the area to find them.
+pub fn render_map(
+    sampler: &TerrainSampler,
+    registry: &BlockRegistry,
+    kind: MapKind,
+    area: MapArea,
+) -> Image {
+    let UVec2 {
+        x: width,
+        y: height,
+    } = area.pixels();
+    let (lowest, highest) = match kind {
+        MapKind::Height => columns(sampler, area)
+            .map(|sample| (sample.height, sample.height))
+            .reduce(|(lowest, highest), (low, high)| (lowest.min(low), highest.max(high)))
+            .unwrap_or_default(),
+        _ => (0, 0),
+    };
+
+    let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+    for sample in columns(sampler, area) {
+        let [r, g, b] = match kind {
+            MapKind::Height => [height_shade(sample.height, lowest, highest); 3],
+            MapKind::Biome => sample.config.map_color,
+            MapKind::Surface => registry
+                .get(sample.surface_block())
+                .map_or([255, 0, 255], |block| block.map_color),
+        };
+        data.extend([r, g, b, 255]);
+    }
+
+    rgba_image(width, height, data)
+}
+
+/// The column under every pixel of the area, row by row
+fn columns(sampler: &TerrainSampler, area: MapArea) -> impl Iterator<Item = ColumnSample> + '_ {
+    let scale = area.scale.max(1);
+    let UVec2 {
+        x: width,
+        y: height,
+    } = area.pixels();
+    (0..height).flat_map(move |row| {
+        (0..width).map(move |column| {
+            sampler.sample(
+                area.min.x + (column * scale) as i32,
+                area.min.y + (row * scale) as i32,
+            )
+        })
+    })
+}
+
+/// Grey level of a height between `lowest` and `highest`, mid grey when the area is flat
+fn height_shade(height: i32, lowest: i32, highest: i32) -> u8 {
+    if highest <= lowest {
+        return 128;
+    }
+    ((height - lowest) as f32 / (highest - lowest) as f32 * 255.0).round() as u8
+}
+
+/// Renders a noise graph in grayscale, -1 black and 1 white, to preview it without a world
+pub fn render_noise(graph: &NoiseGraph, area: MapArea) -> Image {
+    let scale = area.scale.max(1);
+    let UVec2 {
+        x: width,
+        y: height,
+    } = area.pixels();
+    let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+
+    for row in 0..height {
+        for column in 0..width {
+            let value = graph.sample(
+                (area.min.x + (column * scale) as i32) as f32,
+                (area.min.y + (row * scale) as i32) as f32,
+            );
+            let shade = ((value.clamp(-1.0, 1.0) + 1.0) / 2.0 * 255.0).round() as u8;
+            data.extend([shade, shade, shade, 255]);
+        }
+    }
+
+    rgba_image(width, height, data)
+}
+
+fn rgba_image(width: u32, height: u32, data: Vec<u8>) -> Image {
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+fn save_png(image: Image, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    image
+        .try_into_dynamic()
+        .map_err(io::Error::other)?
+        .save(path)
+        .map_err(io::Error::other)
+}
+
+/// Writes `<stem>_height.png`, `<stem>_biome.png` and `<stem>_surface.png`
+pub fn write_maps(
+    stem: &Path,
+    sampler: &TerrainSampler,
+    registry: &BlockRegistry,
+    area: MapArea,
+) -> io::Result<Vec<PathBuf>> {
+    area.validate()?;
+    let mut written = Vec::new();
+    for kind in MapKind::ALL {
+        let mut file_name = stem.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!("_{}.png", kind.name()));
+        let path = stem.with_file_name(file_name);
+
+        save_png(render_map(sampler, registry, kind, area), &path)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Writes the [`render_noise`] preview of a graph to a PNG file
+pub fn write_noise(path: &Path, graph: &NoiseGraph, area: MapArea) -> io::Result<()> {
+    area.validate()?;
+    save_png(render_noise(graph, area), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_generator::world_generator::WorldSettings;
+
+    const AREA: MapArea = MapArea {
+        min: IVec2::new(-40, 24),
+        size: UVec2::new(48, 32),
+        scale: 4,
+    };
+
+    fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * image.width() + x) * 4) as usize;
+        image.data[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn maps_show_the_sampled_columns() {
+        let settings = WorldSettings {
+            seed: 7,
+            ..default()
+        };
+        let sampler = TerrainSampler::new(&settings);
+        let registry = BlockRegistry::default();
+
+        let heights: Vec<i32> = (0..8)
+            .flat_map(|y| (0..12).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                sampler
+                    .sample(AREA.min.x + x * 4, AREA.min.y + y * 4)
+                    .height
+            })
+            .collect();
+        let lowest = *heights.iter().min().unwrap();
+        let highest = *heights.iter().max().unwrap();
+        assert!(lowest < highest, "the test area should not be flat");
+
+        for kind in MapKind::ALL {
+            let image = render_map(&sampler, &registry, kind, AREA);
+            assert_eq!(image.size(), UVec2::new(12, 8), "{kind:?}");
+            assert_eq!(image.data.len(), 12 * 8 * 4);
+
+            for (x, y) in [(0, 0), (11, 0), (5, 3), (11, 7)] {
+                // Each pixel samples the column at its minimum corner
+                let sample = sampler.sample(AREA.min.x + x as i32 * 4, AREA.min.y + y as i32 * 4);
+                let [r, g, b, a] = pixel(&image, x, y);
+                assert_eq!(a, 255);
+                match kind {
+                    MapKind::Height => {
+                        assert!(r == g && g == b);
+                        assert_eq!(r, height_shade(sample.height, lowest, highest));
+                    }
+                    MapKind::Biome => assert_eq!([r, g, b], sample.config.map_color),
+                    MapKind::Surface => assert_eq!(
+                        [r, g, b],
+                        registry.get(sample.surface_block()).unwrap().map_color
+                    ),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn heights_span_the_whole_grey_range() {
+        let sampler = TerrainSampler::new(&WorldSettings::default());
+        let image = render_map(&sampler, &BlockRegistry::default(), MapKind::Height, AREA);
+        let shades: Vec<u8> = image.data.chunks_exact(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(shades.iter().min(), Some(&0));
+        assert_eq!(shades.iter().max(), Some(&255));
+
+        assert_eq!(height_shade(70, 70, 70), 128);
+        assert_eq!(height_shade(80, 60, 100), 128);
+    }
+
+    #[test]
+    fn empty_and_oversized_areas_are_rejected() {
+        assert!(AREA.validate().is_ok());
+        let too_small = MapArea {
+            size: UVec2::new(3, 64),
+            ..AREA
+        };
+        assert_eq!(too_small.pixels(), UVec2::new(0, 16));
+        assert_eq!(
+            too_small.validate().unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        let too_large = MapArea {
+            size: UVec2::new(MAX_MAP_SIZE + 1, 1),
+            scale: 1,
+            ..AREA
+        };
+        assert!(too_large.validate().is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::{
+    mesh::MeshVertexBufferLayoutRef,
+    render_resource::{
+        AsBindGroup, PolygonMode, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+    },
+};
+
+use super::mesher::ATTRIBUTE_VOXEL;
+
+pub const VOXEL_SHADER_PATH: &str = "shaders/voxel.wgsl";
+pub const VOXEL_WIREFRAME_SHADER_PATH: &str = "shaders/voxel_wireframe.wgsl";
+
+pub use settings::VoxelSettings;
+
+// `ShaderType` derives a check function per field that rustc reports as unused
+#[allow(dead_code)]
+mod settings {
+    use bevy::render::render_resource::ShaderType;
+
+    #[derive(Clone, Copy, Debug, ShaderType)]
+    pub struct VoxelSettings {
+        /// How much fully occluded corners are darkened, between 0 and 1
+        pub ao_strength: f32,
+    }
+}
+
+/// Terrain material reading the packed [`ATTRIBUTE_VOXEL`] vertices of chunk meshes.
+/// Faces are shaded by direction, ambient occlusion and light instead of scene lights.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct VoxelMaterial {
+    #[uniform(0)]
+    pub settings: VoxelSettings,
+    /// Block textures, one layer per texture, see
+    /// [`texture_array`](super::textures::texture_array). Until it is set chunks sample
+    /// Bevy's white fallback image and show only their shading.
+    #[texture(1, dimension = "2d_array")]
+    #[sampler(2)]
+    pub textures: Option<Handle<Image>>,
+}
+
+impl Material for VoxelMaterial {
+    fn vertex_shader() -> ShaderRef {
+        VOXEL_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        VOXEL_SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.vertex.buffers = vec![layout
+            .0
+            .get_layout(&[ATTRIBUTE_VOXEL.at_shader_location(0)])?];
+        Ok(())
+    }
+}
+
+/// Flat colored lines over packed chunk meshes. Bevy's wireframes need the position
+/// attribute, so chunks use this material for their debug wireframe instead.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct VoxelWireframeMaterial {
+    #[uniform(0)]
+    pub color: LinearRgba,
+}
+
+impl Material for VoxelWireframeMaterial {
+    fn vertex_shader() -> ShaderRef {
+        VOXEL_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        VOXEL_WIREFRAME_SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.vertex.buffers = vec![layout
+            .0
+            .get_layout(&[ATTRIBUTE_VOXEL.at_shader_location(0)])?];
+        // Leaves out the texture lookups of the shared vertex shader, this material has no textures
+        descriptor.vertex.shader_defs.push("WIREFRAME".into());
+        descriptor.primitive.polygon_mode = PolygonMode::Line;
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            depth_stencil.bias.slope_scale = 1.0;
+        }
+        Ok(())
+    }
+}
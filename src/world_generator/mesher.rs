@@ -0,0 +1,665 @@
+use bevy::prelude::*;
+use bevy::render::{
+    mesh::{Indices, MeshVertexAttribute},
+    primitives::Aabb,
+    render_asset::RenderAssetUsages,
+    render_resource::{PrimitiveTopology, VertexFormat},
+};
+
+use super::block::BlockRegistry;
+use super::chunk::{BlockId, ChunkData, AIR, CHUNK_SIZE};
+use super::coords::{BlockPos, Face, LocalPos};
+
+/// Corners of a face of the unit block, wound counter-clockwise when looking at the face from
+/// outside
+const fn corners(face: Face) -> [[f32; 3]; 4] {
+    match face {
+        Face::PosX => [
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [1.0, 1.0, 1.0],
+            [1.0, 0.0, 1.0],
+        ],
+        Face::NegX => [
+            [0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 1.0, 1.0],
+            [0.0, 1.0, 0.0],
+        ],
+        Face::PosY => [
+            [0.0, 1.0, 0.0],
+            [0.0, 1.0, 1.0],
+            [1.0, 1.0, 1.0],
+            [1.0, 1.0, 0.0],
+        ],
+        Face::NegY => [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0],
+        ],
+        Face::PosZ => [
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0],
+            [0.0, 1.0, 1.0],
+        ],
+        Face::NegZ => [
+            [0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0],
+        ],
+    }
+}
+
+/// Packed vertex attribute read by the voxel shader, see [`PackedVertex`]
+pub const ATTRIBUTE_VOXEL: MeshVertexAttribute =
+    MeshVertexAttribute::new("Voxel", 0x766f_7865, VertexFormat::Uint32x2);
+
+/// Light level of every vertex until the world tracks light
+pub const FULL_LIGHT: u32 = 15;
+
+/// Everything the voxel shader needs for one vertex, packed into two u32s instead of the
+/// 32 bytes of f32 position, normal and uv.
+///
+/// First word: x (6 bits), y (9 bits), z (6 bits) within the chunk, face as its index in
+/// [`Face::ALL`] (3 bits), ambient occlusion 0-3 (2 bits), texture corner u and v (1 bit each).
+/// Second word: texture array layer (16 bits), light 0-15 (4 bits).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedVertex {
+    pub position: UVec3,
+    pub face: Face,
+    pub ao: u32,
+    pub corner: UVec2,
+    pub layer: u32,
+    pub light: u32,
+}
+
+impl PackedVertex {
+    pub fn pack(self) -> [u32; 2] {
+        let p = self.position;
+        [
+            (p.x & 0x3f)
+                | (p.y & 0x1ff) << 6
+                | (p.z & 0x3f) << 15
+                | (self.face as u32) << 21
+                | (self.ao & 0x3) << 24
+                | (self.corner.x & 0x1) << 26
+                | (self.corner.y & 0x1) << 27,
+            (self.layer & 0xffff) | (self.light & 0xf) << 16,
+        ]
+    }
+
+    pub fn unpack([a, b]: [u32; 2]) -> Self {
+        Self {
+            position: UVec3::new(a & 0x3f, a >> 6 & 0x1ff, a >> 15 & 0x3f),
+            face: Face::ALL[(a >> 21 & 0x7) as usize % Face::ALL.len()],
+            ao: a >> 24 & 0x3,
+            corner: UVec2::new(a >> 26 & 0x1, a >> 27 & 0x1),
+            layer: b & 0xffff,
+            light: b >> 16 & 0xf,
+        }
+    }
+}
+
+/// Chunks sharing a face with the one being meshed.
+/// A missing neighbour counts as air, so the faces on that border are emitted.
+#[derive(Clone, Copy, Default)]
+pub struct Neighbours<'a> {
+    pub pos_x: Option<&'a ChunkData>,
+    pub neg_x: Option<&'a ChunkData>,
+    pub pos_y: Option<&'a ChunkData>,
+    pub neg_y: Option<&'a ChunkData>,
+    pub pos_z: Option<&'a ChunkData>,
+    pub neg_z: Option<&'a ChunkData>,
+    /// The chunk is in the bottom layer of the world, nothing is ever visible from below it
+    pub world_bottom: bool,
+}
+
+impl Neighbours<'_> {
+    pub fn get(&self, face: Face) -> Option<&ChunkData> {
+        match face {
+            Face::PosX => self.pos_x,
+            Face::NegX => self.neg_x,
+            Face::PosY => self.pos_y,
+            Face::NegY => self.neg_y,
+            Face::PosZ => self.pos_z,
+            Face::NegZ => self.neg_z,
+        }
+    }
+
+    /// Whether every face of the chunk borders a chunk filled with opaque blocks
+    fn enclose(&self, registry: &BlockRegistry) -> bool {
+        Face::ALL.into_iter().all(|face| {
+            (face == Face::NegY && self.world_bottom)
+                || self
+                    .get(face)
+                    .and_then(ChunkData::uniform)
+                    .is_some_and(|block| registry.is_opaque(block))
+        })
+    }
+}
+
+/// Vertex data of a meshed chunk in chunk-local coordinates, independent of the ECS.
+/// Only holds what the renderer uploads, [`ChunkMeshData::expand`] derives float attributes
+/// for the exporters.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChunkMeshData {
+    pub packed: Vec<[u32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+/// A chunk mesh with the float attributes model formats expect, in chunk-local coordinates
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExpandedMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// Coordinates into the layers laid out side by side, see
+    /// [`texture_strip`](super::textures::texture_strip)
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl ExpandedMesh {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+impl ChunkMeshData {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Every face is a quad made of two triangles
+    pub fn face_count(&self) -> usize {
+        self.indices.len() / 6
+    }
+
+    /// Mesh with only [`ATTRIBUTE_VOXEL`], for the voxel material
+    pub fn into_mesh(self) -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+        )
+        .with_inserted_attribute(ATTRIBUTE_VOXEL, self.packed)
+        .with_inserted_indices(Indices::U32(self.indices))
+    }
+
+    /// Unpacks every vertex into a position, normal and uv into the texture strip of
+    /// `registry`
+    pub fn expand(&self, registry: &BlockRegistry) -> ExpandedMesh {
+        let mut mesh = ExpandedMesh {
+            indices: self.indices.clone(),
+            ..default()
+        };
+        for vertex in self
+            .packed
+            .iter()
+            .map(|packed| PackedVertex::unpack(*packed))
+        {
+            mesh.positions.push(vertex.position.as_vec3().to_array());
+            mesh.normals.push(vertex.face.normal().as_vec3().to_array());
+            mesh.uvs.push(strip_uv(
+                registry,
+                vertex.layer,
+                vertex.corner.as_vec2().to_array(),
+            ));
+        }
+        mesh
+    }
+
+    /// Bounds of a chunk mesh. Bevy derives them from the position attribute,
+    /// which packed meshes do not have.
+    pub fn bounds() -> Aabb {
+        Aabb::from_min_max(Vec3::ZERO, Vec3::splat(CHUNK_SIZE as f32))
+    }
+}
+
+/// Emits one quad for every face of a non-air block that is not hidden by an opaque block,
+/// looking into the neighbouring chunks for faces on the chunk border
+pub fn mesh_chunk(
+    chunk: &ChunkData,
+    neighbours: &Neighbours,
+    registry: &BlockRegistry,
+) -> ChunkMeshData {
+    let mut mesh = ChunkMeshData::default();
+
+    // Air has no faces, and a chunk of one opaque block only has faces on its border, which
+    // are all hidden when the neighbours are solid too. Skips the sky and deep underground.
+    if let Some(block) = chunk.uniform() {
+        if block == AIR || (registry.is_opaque(block) && neighbours.enclose(registry)) {
+            return mesh;
+        }
+    }
+
+    for local in LocalPos::all() {
+        let block = chunk.get(local);
+        if block == AIR {
+            continue;
+        }
+        let faces = registry.face_layers(block);
+
+        for face in Face::ALL {
+            let front = local.as_ivec3() + face.normal();
+            // Faces between two blocks of the same kind are hidden even when they are
+            // see-through, so water and glass have no inner walls
+            if is_hidden(chunk, neighbours, registry, front)
+                || block_at(chunk, neighbours, front) == Some(block)
+            {
+                continue;
+            }
+
+            let layer = *faces.get(face);
+            let cell = mesh.packed.len() as u32;
+            let mut ao = [0; 4];
+            for (i, corner) in corners(face).into_iter().enumerate() {
+                ao[i] = corner_ao(chunk, neighbours, registry, front, face, corner);
+                mesh.packed.push(
+                    PackedVertex {
+                        position: (local.as_vec3() + Vec3::from(corner)).as_uvec3(),
+                        face,
+                        ao: ao[i],
+                        corner: Vec2::from(face_uv(face, corner)).as_uvec2(),
+                        layer,
+                        light: FULL_LIGHT,
+                    }
+                    .pack(),
+                );
+            }
+            // Split the quad along the brighter diagonal so occlusion interpolates evenly
+            // instead of showing the triangle seam
+            if ao[0] + ao[2] < ao[1] + ao[3] {
+                mesh.indices
+                    .extend([cell + 1, cell + 2, cell + 3, cell + 1, cell + 3, cell]);
+            } else {
+                mesh.indices
+                    .extend([cell, cell + 1, cell + 2, cell, cell + 2, cell + 3]);
+            }
+        }
+    }
+
+    mesh
+}
+
+/// Whether a face looking into `pos`, relative to the chunk origin, is covered by an opaque
+/// block
+fn is_hidden(
+    chunk: &ChunkData,
+    neighbours: &Neighbours,
+    registry: &BlockRegistry,
+    pos: IVec3,
+) -> bool {
+    // Nothing is ever visible from below the world
+    if pos.y < 0 && neighbours.world_bottom {
+        return true;
+    }
+    block_at(chunk, neighbours, pos).is_some_and(|block| registry.is_opaque(block))
+}
+
+/// Block at a position relative to the chunk origin that may lie in a bordering chunk, `None`
+/// outside the loaded neighbourhood
+fn block_at(chunk: &ChunkData, neighbours: &Neighbours, pos: IVec3) -> Option<BlockId> {
+    // Relative to the chunk origin, the chunk of a block is the direction of its neighbour
+    let pos = BlockPos(pos);
+    let direction = pos.chunk().0;
+    let neighbour = if direction == IVec3::ZERO {
+        chunk
+    } else {
+        // Diagonal chunks are not part of the neighbourhood, only occlusion looks that far
+        neighbours.get(Face::from_normal(direction)?)?
+    };
+    Some(neighbour.get(pos.local()))
+}
+
+/// Ambient occlusion of a face corner from the three blocks around it in front of the face,
+/// 3 when open and 0 when tucked into an inner corner
+fn corner_ao(
+    chunk: &ChunkData,
+    neighbours: &Neighbours,
+    registry: &BlockRegistry,
+    front: IVec3,
+    face: Face,
+    corner: [f32; 3],
+) -> u32 {
+    let mut sides = [IVec3::ZERO; 2];
+    let mut tangents = (0..3).filter(|&axis| axis != face.axis());
+    for side in &mut sides {
+        let axis = tangents.next().unwrap_or_default();
+        side[axis] = if corner[axis] > 0.0 { 1 } else { -1 };
+    }
+    let occludes = |offset: IVec3| {
+        let pos = front + offset;
+        // Below the world counts as open so the bottom of the world is not darkened
+        !(pos.y < 0 && neighbours.world_bottom) && is_hidden(chunk, neighbours, registry, pos)
+    };
+
+    let (side_a, side_b) = (occludes(sides[0]), occludes(sides[1]));
+    if side_a && side_b {
+        return 0;
+    }
+    3 - side_a as u32 - side_b as u32 - occludes(sides[0] + sides[1]) as u32
+}
+
+fn face_uv(face: Face, corner: [f32; 3]) -> [f32; 2] {
+    match face.axis() {
+        1 => [corner[0], corner[2]],
+        0 => [corner[2], 1.0 - corner[1]],
+        _ => [corner[0], 1.0 - corner[1]],
+    }
+}
+
+fn strip_uv(registry: &BlockRegistry, layer: u32, uv: [f32; 2]) -> [f32; 2] {
+    let layers = registry.textures().len().max(1) as f32;
+    [(layer as f32 + uv[0]) / layers, uv[1]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_generator::block::{BlockDefinition, BlockFaces, RenderLayer};
+    use crate::world_generator::chunk::STONE;
+
+    fn chunk_with(blocks: &[(usize, usize, usize)]) -> ChunkData {
+        let mut chunk = ChunkData::empty();
+        for &(x, y, z) in blocks {
+            chunk.set(LocalPos::new(x, y, z), STONE);
+        }
+        chunk
+    }
+
+    fn mesh(chunk: &ChunkData, neighbours: &Neighbours) -> ChunkMeshData {
+        mesh_chunk(chunk, neighbours, &BlockRegistry::default())
+    }
+
+    fn vertices(mesh: &ChunkMeshData) -> impl Iterator<Item = PackedVertex> + '_ {
+        mesh.packed
+            .iter()
+            .map(|packed| PackedVertex::unpack(*packed))
+    }
+
+    fn has_face(mesh: &ChunkMeshData, face: Face) -> bool {
+        vertices(mesh).any(|vertex| vertex.face == face)
+    }
+
+    fn has_face_on_border(mesh: &ChunkMeshData, face: Face) -> bool {
+        let axis = face.axis();
+        let border = if face.normal()[axis] > 0 {
+            CHUNK_SIZE as u32
+        } else {
+            0
+        };
+        vertices(mesh).any(|vertex| vertex.face == face && vertex.position[axis] == border)
+    }
+
+    #[test]
+    fn empty_chunk_has_no_faces() {
+        let mesh = mesh(&ChunkData::empty(), &Neighbours::default());
+        assert!(mesh.is_empty());
+        assert!(mesh.packed.is_empty());
+    }
+
+    #[test]
+    fn floating_block_has_six_faces() {
+        let mesh = mesh(&chunk_with(&[(4, 4, 4)]), &Neighbours::default());
+        assert_eq!(mesh.face_count(), 6);
+        assert_eq!(mesh.packed.len(), 24);
+        assert_eq!(mesh.indices.len(), 36);
+
+        let expanded = mesh.expand(&BlockRegistry::default());
+        assert_eq!(expanded.positions.len(), 24);
+        assert_eq!(expanded.normals.len(), 24);
+        assert_eq!(expanded.uvs.len(), 24);
+        assert_eq!(expanded.indices, mesh.indices);
+    }
+
+    #[test]
+    fn bottom_of_world_is_culled() {
+        let chunk = chunk_with(&[(4, 0, 4)]);
+        let bottom = Neighbours {
+            world_bottom: true,
+            ..default()
+        };
+        let mesh = self::mesh(&chunk, &bottom);
+        assert_eq!(mesh.face_count(), 5);
+        assert!(!has_face(&mesh, Face::NegY));
+
+        // Higher up the chunk below is not loaded yet, so the face stays
+        assert!(has_face(
+            &self::mesh(&chunk, &Neighbours::default()),
+            Face::NegY
+        ));
+    }
+
+    #[test]
+    fn shared_faces_are_culled() {
+        let mesh = mesh(&chunk_with(&[(4, 4, 4), (5, 4, 4)]), &Neighbours::default());
+        assert_eq!(mesh.face_count(), 10);
+
+        let column = chunk_with(&[(4, 4, 4), (4, 5, 4), (4, 6, 4)]);
+        assert_eq!(self::mesh(&column, &Neighbours::default()).face_count(), 14);
+    }
+
+    fn full_chunk(block: BlockId) -> ChunkData {
+        let mut chunk = ChunkData::empty();
+        for pos in LocalPos::all() {
+            chunk.set(pos, block);
+        }
+        chunk
+    }
+
+    #[test]
+    fn full_chunk_only_shows_its_shell() {
+        let mesh = mesh(&full_chunk(STONE), &Neighbours::default());
+        assert_eq!(mesh.face_count(), 6 * CHUNK_SIZE * CHUNK_SIZE);
+    }
+
+    #[test]
+    fn buried_chunk_has_no_faces() {
+        let stone = full_chunk(STONE);
+        let mut neighbours = Neighbours {
+            pos_x: Some(&stone),
+            neg_x: Some(&stone),
+            pos_y: Some(&stone),
+            pos_z: Some(&stone),
+            neg_z: Some(&stone),
+            world_bottom: true,
+            ..default()
+        };
+        assert!(mesh(&stone, &neighbours).is_empty());
+
+        // One open side is enough to mesh the whole chunk
+        let air = ChunkData::empty();
+        neighbours.pos_y = Some(&air);
+        assert_eq!(
+            mesh(&stone, &neighbours).face_count(),
+            CHUNK_SIZE * CHUNK_SIZE
+        );
+    }
+
+    #[test]
+    fn triangles_wind_counter_clockwise_around_their_normal() {
+        let mesh = mesh(&chunk_with(&[(4, 4, 4)]), &Neighbours::default())
+            .expand(&BlockRegistry::default());
+
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(mesh.positions[triangle[i] as usize]));
+            let normal = Vec3::from(mesh.normals[triangle[0] as usize]);
+            let winding = (b - a).cross(c - a).normalize();
+            assert!(winding.abs_diff_eq(normal, 1e-6), "{winding} != {normal}");
+        }
+    }
+
+    #[test]
+    fn faces_sit_on_the_block_surface() {
+        let mesh = mesh(&chunk_with(&[(4, 4, 4)]), &Neighbours::default())
+            .expand(&BlockRegistry::default());
+
+        let center = Vec3::splat(4.5);
+        for (position, normal) in mesh.positions.iter().zip(&mesh.normals) {
+            let offset = Vec3::from(*position) - center;
+            assert_eq!(offset.dot(Vec3::from(*normal)), 0.5);
+        }
+    }
+
+    #[test]
+    fn border_faces_without_neighbours_are_emitted() {
+        let last = CHUNK_SIZE - 1;
+        let chunk = chunk_with(&[(0, 4, 4), (last, 4, 4), (4, 4, 0), (4, 4, last)]);
+
+        let mesh = mesh(&chunk, &Neighbours::default());
+        assert_eq!(mesh.face_count(), 24);
+    }
+
+    #[test]
+    fn border_faces_are_culled_by_solid_neighbours() {
+        let last = CHUNK_SIZE - 1;
+        let chunk = chunk_with(&[(last, 4, 4), (4, 4, 0)]);
+        let pos_x = chunk_with(&[(0, 4, 4)]);
+        let neg_z = chunk_with(&[(4, 4, last)]);
+
+        let mesh = mesh(
+            &chunk,
+            &Neighbours {
+                pos_x: Some(&pos_x),
+                neg_z: Some(&neg_z),
+                ..default()
+            },
+        );
+        assert_eq!(mesh.face_count(), 10);
+        assert!(!has_face_on_border(&mesh, Face::PosX));
+        assert!(!has_face_on_border(&mesh, Face::NegZ));
+    }
+
+    #[test]
+    fn border_faces_facing_empty_neighbours_are_emitted() {
+        let chunk = chunk_with(&[(0, 4, 4)]);
+        let neg_x = ChunkData::empty();
+
+        let mesh = mesh(
+            &chunk,
+            &Neighbours {
+                neg_x: Some(&neg_x),
+                ..default()
+            },
+        );
+        assert_eq!(mesh.face_count(), 6);
+        assert!(has_face(&mesh, Face::NegX));
+    }
+
+    #[test]
+    fn non_opaque_blocks_only_cull_their_own_kind() {
+        let mut registry = BlockRegistry::default();
+        let glass = registry.register(BlockDefinition {
+            name: "glass".to_string(),
+            display_name: "Glass".to_string(),
+            render_layer: RenderLayer::Cutout,
+            solid: true,
+            faces: BlockFaces::all("texture".to_string()),
+            map_color: [255, 255, 255],
+        });
+        let mut chunk = chunk_with(&[(4, 4, 4)]);
+        chunk.set(LocalPos::new(5, 4, 4), glass);
+
+        let mesh = mesh_chunk(&chunk, &Neighbours::default(), &registry);
+        // The stone keeps its face towards the glass, the glass is hidden by the stone
+        assert_eq!(mesh.face_count(), 11);
+
+        // Glass next to glass has no face in between
+        chunk.set(LocalPos::new(6, 4, 4), glass);
+        let mesh = mesh_chunk(&chunk, &Neighbours::default(), &registry);
+        assert_eq!(mesh.face_count(), 15);
+    }
+
+    #[test]
+    fn packed_vertices_round_trip() {
+        let vertex = PackedVertex {
+            position: UVec3::new(32, 64, 17),
+            face: Face::NegZ,
+            ao: 2,
+            corner: UVec2::new(1, 0),
+            layer: 513,
+            light: 9,
+        };
+        assert_eq!(PackedVertex::unpack(vertex.pack()), vertex);
+    }
+
+    #[test]
+    fn expanded_attributes_match_the_packed_vertices() {
+        let mesh = mesh(
+            &chunk_with(&[(4, 4, 4), (31, 31, 0)]),
+            &Neighbours::default(),
+        );
+        let expanded = mesh.expand(&BlockRegistry::default());
+        assert_eq!(mesh.packed.len(), expanded.positions.len());
+        for (i, vertex) in vertices(&mesh).enumerate() {
+            assert_eq!(vertex.position.as_vec3().to_array(), expanded.positions[i]);
+            assert_eq!(
+                vertex.face.normal().as_vec3().to_array(),
+                expanded.normals[i]
+            );
+        }
+    }
+
+    #[test]
+    fn corners_next_to_blocks_are_occluded() {
+        // A floor with a wall along one edge of the block in the middle
+        let mut blocks: Vec<_> = (3..6)
+            .flat_map(|x| (3..6).map(move |z| (x, 4, z)))
+            .collect();
+        blocks.push((5, 5, 4));
+        let mesh = mesh(&chunk_with(&blocks), &Neighbours::default());
+
+        let ao_at = |corner: [f32; 3]| {
+            vertices(&mesh)
+                .find(|v| v.face == Face::PosY && v.position.as_vec3().to_array() == corner)
+                .unwrap()
+                .ao
+        };
+        assert_eq!(ao_at([4.0, 5.0, 4.0]), 3);
+        assert_eq!(ao_at([5.0, 5.0, 4.0]), 2);
+        assert_eq!(ao_at([5.0, 5.0, 5.0]), 2);
+    }
+
+    #[test]
+    fn faces_sample_their_texture_layer() {
+        let mut registry = BlockRegistry::new();
+        let top = registry.add_texture("b", "b.png");
+        let bottom = registry.add_texture("c", "c.png");
+        let layers = registry.textures().len() as f32;
+        let block = registry.register(BlockDefinition {
+            name: "test".to_string(),
+            display_name: "Test".to_string(),
+            render_layer: RenderLayer::Opaque,
+            solid: true,
+            faces: BlockFaces {
+                top: "b".to_string(),
+                bottom: "c".to_string(),
+                side: "missing".to_string(),
+            },
+            map_color: [255, 255, 255],
+        });
+        let mut chunk = ChunkData::empty();
+        chunk.set(LocalPos::new(4, 4, 4), block);
+
+        let mesh = mesh_chunk(&chunk, &Neighbours::default(), &registry);
+        let expanded = mesh.expand(&registry);
+        for ((uv, normal), packed) in expanded.uvs.iter().zip(&expanded.normals).zip(&mesh.packed) {
+            // Unknown names fall back to the first layer
+            let layer = if normal[1] > 0.0 {
+                top
+            } else if normal[1] < 0.0 {
+                bottom
+            } else {
+                0
+            };
+            assert_eq!(PackedVertex::unpack(*packed).layer, layer);
+            let (min, max) = (layer as f32 / layers, (layer + 1) as f32 / layers);
+            assert!(
+                (min..=max).contains(&uv[0]) && (0.0..=1.0).contains(&uv[1]),
+                "{uv:?} outside layer {layer}"
+            );
+        }
+    }
+}
@@ -1 +1,17 @@
+pub mod block;
+pub mod chunk;
+pub mod config;
+pub mod coords;
+pub mod export;
+pub mod maps;
+pub mod material;
+pub mod mesher;
+pub mod noise_graph;
+pub mod region;
+pub mod render;
+pub mod resource_pack;
+pub mod ron_asset;
+pub mod terrain;
+pub mod textures;
+#[allow(clippy::module_inception)]
 pub mod world_generator;
@@ -0,0 +1,440 @@
+use std::io;
+use std::path::Path;
+
+use fastnoise_lite::{DomainWarpType, FastNoiseLite, FractalType, NoiseType};
+use serde::{Deserialize, Serialize};
+
+/// Basis noise of a [`NoiseLayer`], see [`NoiseType`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoiseKind {
+    #[default]
+    OpenSimplex2,
+    OpenSimplex2S,
+    Cellular,
+    Perlin,
+    ValueCubic,
+    Value,
+}
+
+impl From<NoiseKind> for NoiseType {
+    fn from(kind: NoiseKind) -> Self {
+        match kind {
+            NoiseKind::OpenSimplex2 => NoiseType::OpenSimplex2,
+            NoiseKind::OpenSimplex2S => NoiseType::OpenSimplex2S,
+            NoiseKind::Cellular => NoiseType::Cellular,
+            NoiseKind::Perlin => NoiseType::Perlin,
+            NoiseKind::ValueCubic => NoiseType::ValueCubic,
+            NoiseKind::Value => NoiseType::Value,
+        }
+    }
+}
+
+/// How the octaves of a [`NoiseLayer`] are combined
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Fractal {
+    /// A single octave
+    None,
+    #[default]
+    FBm,
+    /// Sharp crests where the noise crosses zero, for mountain ridges
+    Ridged,
+    /// Octaves folded back and forth, for bands and canyons
+    PingPong,
+}
+
+impl From<Fractal> for FractalType {
+    fn from(fractal: Fractal) -> Self {
+        match fractal {
+            Fractal::None => FractalType::None,
+            Fractal::FBm => FractalType::FBm,
+            Fractal::Ridged => FractalType::Ridged,
+            Fractal::PingPong => FractalType::PingPong,
+        }
+    }
+}
+
+fn default_lacunarity() -> f32 {
+    2.0
+}
+
+fn default_gain() -> f32 {
+    0.5
+}
+
+/// Fractal noise feeding one input of the generator
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoiseLayer {
+    /// Added to the world seed so layers with the same parameters still differ
+    pub seed_offset: i32,
+    #[serde(default)]
+    pub kind: NoiseKind,
+    #[serde(default)]
+    pub fractal: Fractal,
+    pub frequency: f32,
+    pub octaves: i32,
+    /// Frequency multiplier between octaves
+    #[serde(default = "default_lacunarity")]
+    pub lacunarity: f32,
+    /// Amplitude multiplier between octaves
+    #[serde(default = "default_gain")]
+    pub gain: f32,
+    /// How much higher octaves are damped where the lower ones are low, 0 to 1
+    #[serde(default)]
+    pub weighted_strength: f32,
+}
+
+impl Default for NoiseLayer {
+    fn default() -> Self {
+        Self {
+            seed_offset: 0,
+            kind: NoiseKind::default(),
+            fractal: Fractal::default(),
+            frequency: 0.01,
+            octaves: 3,
+            lacunarity: default_lacunarity(),
+            gain: default_gain(),
+            weighted_strength: 0.0,
+        }
+    }
+}
+
+impl NoiseLayer {
+    /// Noise in -1..1
+    pub fn noise(&self, seed: i32) -> FastNoiseLite {
+        let mut noise = FastNoiseLite::with_seed(seed.wrapping_add(self.seed_offset));
+        noise.set_noise_type(Some(self.kind.into()));
+        noise.set_fractal_type(Some(self.fractal.into()));
+        noise.set_frequency(Some(self.frequency));
+        noise.set_fractal_octaves(Some(self.octaves));
+        noise.set_fractal_lacunarity(Some(self.lacunarity));
+        noise.set_fractal_gain(Some(self.gain));
+        noise.set_fractal_weighted_strength(Some(self.weighted_strength));
+        noise
+    }
+}
+
+/// Displacement of the sample position before sampling another node, see [`DomainWarpType`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DomainWarp {
+    pub seed_offset: i32,
+    pub frequency: f32,
+    /// Largest displacement in blocks
+    pub amplitude: f32,
+    /// More than one octave warps progressively, each octave warping the result of the last
+    #[serde(default = "one_octave")]
+    pub octaves: i32,
+}
+
+fn one_octave() -> i32 {
+    1
+}
+
+impl DomainWarp {
+    fn noise(&self, seed: i32) -> FastNoiseLite {
+        let mut noise = FastNoiseLite::with_seed(seed.wrapping_add(self.seed_offset));
+        noise.set_domain_warp_type(Some(DomainWarpType::OpenSimplex2));
+        noise.set_domain_warp_amp(Some(self.amplitude));
+        noise.set_frequency(Some(self.frequency));
+        if self.octaves > 1 {
+            noise.set_fractal_type(Some(FractalType::DomainWarpProgressive));
+            noise.set_fractal_octaves(Some(self.octaves));
+        }
+        noise
+    }
+}
+
+/// One node of a noise graph, sampled on the xz plane. Nodes nest to compose a function, for
+/// example ridged mountains masked by a warped continent noise:
+///
+/// ```ron
+/// Select(
+///     selector: Warp(
+///         input: Noise((seed_offset: 2, frequency: 0.004, octaves: 3)),
+///         warp: (seed_offset: 3, frequency: 0.01, amplitude: 40.0),
+///     ),
+///     low: Constant(-0.6),
+///     high: Noise((seed_offset: 0, fractal: Ridged, frequency: 0.02, octaves: 5)),
+///     threshold: 0.0,
+///     falloff: 0.2,
+/// )
+/// ```
+///
+/// Sources produce values in -1..1 and the generator maps that range to its height range,
+/// so keeping the output of the graph in -1..1 keeps the terrain within bounds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum NoiseNode {
+    Noise(NoiseLayer),
+    Constant(f32),
+    /// Sum of the inputs
+    Add(Vec<NoiseNode>),
+    /// Product of the inputs
+    Mul(Vec<NoiseNode>),
+    Clamp {
+        input: Box<NoiseNode>,
+        min: f32,
+        max: f32,
+    },
+    /// Piecewise linear remap through `(input, output)` points sorted by input. Values outside
+    /// the points take the output of the nearest end.
+    Spline {
+        input: Box<NoiseNode>,
+        points: Vec<(f32, f32)>,
+    },
+    /// Samples the input at a displaced position
+    Warp {
+        input: Box<NoiseNode>,
+        warp: DomainWarp,
+    },
+    /// `low` where the selector is below the threshold, `high` above it, blended linearly
+    /// within `falloff` of the threshold
+    Select {
+        selector: Box<NoiseNode>,
+        low: Box<NoiseNode>,
+        high: Box<NoiseNode>,
+        threshold: f32,
+        #[serde(default)]
+        falloff: f32,
+    },
+    /// Quantizes -1..1 into `steps` flat levels. With `smoothness` 0 the steps are sheer
+    /// cliffs, towards 1 each riser slopes more until the input passes through unchanged.
+    Terrace {
+        input: Box<NoiseNode>,
+        steps: u32,
+        #[serde(default)]
+        smoothness: f32,
+    },
+}
+
+impl NoiseNode {
+    /// Reads a graph from a RON file outside of the asset server, for the headless tools
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+impl From<NoiseLayer> for NoiseNode {
+    fn from(layer: NoiseLayer) -> Self {
+        NoiseNode::Noise(layer)
+    }
+}
+
+/// A [`NoiseNode`] tree with its noise sources created for one seed, ready to sample
+pub struct NoiseGraph {
+    root: Node,
+}
+
+enum Node {
+    Noise(FastNoiseLite),
+    Constant(f32),
+    Add(Vec<Node>),
+    Mul(Vec<Node>),
+    Clamp(Box<Node>, f32, f32),
+    Spline(Box<Node>, Vec<(f32, f32)>),
+    Warp(Box<Node>, FastNoiseLite),
+    Select {
+        selector: Box<Node>,
+        low: Box<Node>,
+        high: Box<Node>,
+        threshold: f32,
+        falloff: f32,
+    },
+    Terrace(Box<Node>, f32, f32),
+}
+
+impl NoiseGraph {
+    pub fn new(node: &NoiseNode, seed: i32) -> Self {
+        Self {
+            root: Node::new(node, seed),
+        }
+    }
+
+    pub fn sample(&self, x: f32, z: f32) -> f32 {
+        self.root.sample(x, z)
+    }
+}
+
+impl Node {
+    fn new(node: &NoiseNode, seed: i32) -> Self {
+        let boxed = |node: &NoiseNode| Box::new(Node::new(node, seed));
+        match node {
+            NoiseNode::Noise(layer) => Node::Noise(layer.noise(seed)),
+            NoiseNode::Constant(value) => Node::Constant(*value),
+            NoiseNode::Add(inputs) => {
+                Node::Add(inputs.iter().map(|node| Node::new(node, seed)).collect())
+            }
+            NoiseNode::Mul(inputs) => {
+                Node::Mul(inputs.iter().map(|node| Node::new(node, seed)).collect())
+            }
+            NoiseNode::Clamp { input, min, max } => Node::Clamp(boxed(input), *min, *max),
+            NoiseNode::Spline { input, points } => {
+                let mut points = points.clone();
+                points.sort_by(|a, b| a.0.total_cmp(&b.0));
+                Node::Spline(boxed(input), points)
+            }
+            NoiseNode::Warp { input, warp } => Node::Warp(boxed(input), warp.noise(seed)),
+            NoiseNode::Select {
+                selector,
+                low,
+                high,
+                threshold,
+                falloff,
+            } => Node::Select {
+                selector: boxed(selector),
+                low: boxed(low),
+                high: boxed(high),
+                threshold: *threshold,
+                falloff: falloff.max(0.0),
+            },
+            NoiseNode::Terrace {
+                input,
+                steps,
+                smoothness,
+            } => Node::Terrace(
+                boxed(input),
+                (*steps).max(1) as f32,
+                smoothness.clamp(0.0, 1.0),
+            ),
+        }
+    }
+
+    fn sample(&self, x: f32, z: f32) -> f32 {
+        match self {
+            Node::Noise(noise) => noise.get_noise_2d(x, z),
+            Node::Constant(value) => *value,
+            Node::Add(inputs) => inputs.iter().map(|node| node.sample(x, z)).sum(),
+            Node::Mul(inputs) => inputs.iter().map(|node| node.sample(x, z)).product(),
+            Node::Clamp(input, min, max) => input.sample(x, z).clamp(*min, *max),
+            Node::Spline(input, points) => spline(points, input.sample(x, z)),
+            Node::Warp(input, warp) => {
+                let (x, z) = warp.domain_warp_2d(x, z);
+                input.sample(x, z)
+            }
+            Node::Select {
+                selector,
+                low,
+                high,
+                threshold,
+                falloff,
+            } => {
+                let selector = selector.sample(x, z);
+                if selector <= threshold - falloff {
+                    low.sample(x, z)
+                } else if selector >= threshold + falloff {
+                    high.sample(x, z)
+                } else {
+                    let t = (selector - threshold + falloff) / (2.0 * falloff);
+                    low.sample(x, z) * (1.0 - t) + high.sample(x, z) * t
+                }
+            }
+            Node::Terrace(input, steps, smoothness) => {
+                terrace(input.sample(x, z), *steps, *smoothness)
+            }
+        }
+    }
+}
+
+fn spline(points: &[(f32, f32)], value: f32) -> f32 {
+    let Some(&(first_in, first_out)) = points.first() else {
+        return value;
+    };
+    if value <= first_in {
+        return first_out;
+    }
+    for pair in points.windows(2) {
+        let ((in0, out0), (in1, out1)) = (pair[0], pair[1]);
+        if value <= in1 {
+            let t = if in1 > in0 {
+                (value - in0) / (in1 - in0)
+            } else {
+                1.0
+            };
+            return out0 + (out1 - out0) * t;
+        }
+    }
+    points[points.len() - 1].1
+}
+
+fn terrace(value: f32, steps: f32, smoothness: f32) -> f32 {
+    let scaled = (value.clamp(-1.0, 1.0) + 1.0) / 2.0 * steps;
+    let level = scaled.floor().min(steps - 1.0);
+    let riser = if smoothness > 0.0 {
+        (scaled - level).powf(1.0 / smoothness)
+    } else {
+        0.0
+    };
+    (level + riser) / steps * 2.0 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_layer() -> NoiseLayer {
+        NoiseLayer {
+            frequency: 0.05,
+            ..NoiseLayer::default()
+        }
+    }
+
+    #[test]
+    fn noise_node_matches_its_layer() {
+        let layer = NoiseLayer {
+            fractal: Fractal::Ridged,
+            ..default_layer()
+        };
+        let graph = NoiseGraph::new(&layer.clone().into(), 7);
+        let noise = layer.noise(7);
+        for (x, z) in [(0.0, 0.0), (13.0, -40.0), (-250.0, 99.0)] {
+            assert_eq!(graph.sample(x, z), noise.get_noise_2d(x, z));
+        }
+    }
+
+    #[test]
+    fn combinators_compose() {
+        let node: NoiseNode = ron::from_str(
+            "Clamp(
+                input: Add([Constant(0.5), Mul([Constant(2.0), Constant(0.5)])]),
+                min: -1.0,
+                max: 1.0,
+            )",
+        )
+        .unwrap();
+        assert_eq!(NoiseGraph::new(&node, 0).sample(0.0, 0.0), 1.0);
+
+        let select = |selector: f32| {
+            let node = NoiseNode::Select {
+                selector: Box::new(NoiseNode::Constant(selector)),
+                low: Box::new(NoiseNode::Constant(-1.0)),
+                high: Box::new(NoiseNode::Constant(1.0)),
+                threshold: 0.0,
+                falloff: 0.5,
+            };
+            NoiseGraph::new(&node, 0).sample(0.0, 0.0)
+        };
+        assert_eq!(select(-0.6), -1.0);
+        assert_eq!(select(0.0), 0.0);
+        assert_eq!(select(0.25), 0.5);
+        assert_eq!(select(0.9), 1.0);
+    }
+
+    #[test]
+    fn spline_interpolates_and_clamps_to_its_ends() {
+        let points = [(-1.0, -1.0), (0.0, 0.5), (1.0, 0.6)];
+        assert_eq!(spline(&points, -2.0), -1.0);
+        assert_eq!(spline(&points, -0.5), -0.25);
+        assert_eq!(spline(&points, 0.5), 0.55);
+        assert_eq!(spline(&points, 3.0), 0.6);
+        assert_eq!(spline(&[], 0.3), 0.3);
+    }
+
+    #[test]
+    fn terrace_snaps_to_levels() {
+        assert_eq!(terrace(-1.0, 4.0, 0.0), -1.0);
+        assert_eq!(terrace(-0.1, 4.0, 0.0), -0.5);
+        assert_eq!(terrace(0.4, 4.0, 0.0), 0.0);
+        assert_eq!(terrace(1.0, 4.0, 0.0), 0.5);
+        // Fully smooth terraces pass the input through
+        assert!((terrace(0.3, 4.0, 1.0) - 0.3).abs() < 1e-6);
+    }
+}
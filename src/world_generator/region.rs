@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use super::chunk::{BlockId, ChunkData, AIR, CHUNK_SIZE, CHUNK_VOLUME, WORLD_MAX_Y, WORLD_MIN_Y};
+use super::coords::{ChunkPos, LocalPos};
+use super::world_generator::VoxelWorld;
+
+const MAGIC: &[u8; 4] = b"BMCR";
+const VERSION: u32 = 2;
+
+/// Writes every chunk of the world to a little-endian region file.
+/// Chunks are written sorted by position so the same world always produces the same bytes.
+pub fn write_region(path: impl AsRef<Path>, world: &VoxelWorld) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+
+    out.write_all(MAGIC)?;
+    for value in [VERSION, CHUNK_SIZE as u32] {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    for value in [WORLD_MIN_Y, WORLD_MAX_Y] {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    out.write_all(&(world.chunks.len() as u32).to_le_bytes())?;
+
+    let mut positions: Vec<&ChunkPos> = world.chunks.keys().collect();
+    positions.sort();
+    for pos in positions {
+        for value in pos.0.to_array() {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        for block in world.chunks[pos].blocks() {
+            out.write_all(&block.to_le_bytes())?;
+        }
+    }
+
+    out.flush()
+}
+
+pub fn read_region(path: impl AsRef<Path>) -> io::Result<VoxelWorld> {
+    let mut input = BufReader::new(File::open(path)?);
+
+    let mut magic = [0; 4];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a region file"));
+    }
+    if read_u32(&mut input)? != VERSION {
+        return Err(invalid_data("unsupported region version"));
+    }
+    if read_u32(&mut input)? != CHUNK_SIZE as u32
+        || read_u32(&mut input)? as i32 != WORLD_MIN_Y
+        || read_u32(&mut input)? as i32 != WORLD_MAX_Y
+    {
+        return Err(invalid_data(
+            "region was written with different chunk or world dimensions",
+        ));
+    }
+
+    let mut world = VoxelWorld::default();
+    let count = read_u32(&mut input)?;
+    let mut bytes = vec![0; CHUNK_VOLUME * std::mem::size_of::<BlockId>()];
+    for _ in 0..count {
+        let pos = ChunkPos::new(
+            read_u32(&mut input)? as i32,
+            read_u32(&mut input)? as i32,
+            read_u32(&mut input)? as i32,
+        );
+        input.read_exact(&mut bytes)?;
+        let blocks = bytes
+            .chunks_exact(2)
+            .map(|pair| BlockId::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let chunk =
+            ChunkData::from_blocks(blocks).ok_or_else(|| invalid_data("truncated chunk"))?;
+        world.chunks.insert(pos, chunk);
+    }
+
+    Ok(world)
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Summary of the blocks in a world, used by the headless CLI
+#[derive(Debug, Default)]
+pub struct RegionStats {
+    pub chunks: usize,
+    pub solid_blocks: usize,
+    /// Bytes used by the block storage of every chunk
+    pub memory_usage: usize,
+    /// Number of blocks of each id, indexed by block id
+    pub block_counts: Vec<usize>,
+    /// Lowest and highest block y above the top block of a column, over columns with blocks
+    pub min_surface_height: i32,
+    pub max_surface_height: i32,
+}
+
+impl RegionStats {
+    pub fn collect(world: &VoxelWorld) -> Self {
+        let mut stats = RegionStats {
+            chunks: world.chunks.len(),
+            ..default()
+        };
+
+        // Chunks are stacked, so the surface of a column is the highest top among them
+        let mut surfaces: HashMap<IVec2, i32> = HashMap::new();
+        for (pos, chunk) in &world.chunks {
+            stats.memory_usage += chunk.memory_usage();
+            for block in chunk.blocks() {
+                if block as usize >= stats.block_counts.len() {
+                    stats.block_counts.resize(block as usize + 1, 0);
+                }
+                stats.block_counts[block as usize] += 1;
+                if block != AIR {
+                    stats.solid_blocks += 1;
+                }
+            }
+            // The bottom layer, one position per column
+            for local in LocalPos::all().take(CHUNK_SIZE * CHUNK_SIZE) {
+                let height = chunk.surface_height(local);
+                if height == 0 {
+                    continue;
+                }
+                let bottom = pos.block(local).0;
+                let top = bottom.y + height;
+                let surface = surfaces.entry(bottom.xz()).or_insert(top);
+                *surface = (*surface).max(top);
+            }
+        }
+
+        stats.min_surface_height = surfaces.values().copied().min().unwrap_or(0);
+        stats.max_surface_height = surfaces.values().copied().max().unwrap_or(0);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::world_generator::chunk::{DIRT, STONE};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("region-{}-{name}.bin", std::process::id()))
+    }
+
+    fn small_world() -> VoxelWorld {
+        let mut world = VoxelWorld::default();
+        for (i, pos) in [
+            ChunkPos::new(0, 0, 0),
+            ChunkPos::new(-1, 2, 0),
+            ChunkPos::new(3, -2, -7),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut chunk = ChunkData::empty();
+            chunk.set(LocalPos::new(i, 0, 0), STONE);
+            chunk.set(LocalPos::new(0, CHUNK_SIZE - 1, i), DIRT);
+            world.chunks.insert(pos, chunk);
+        }
+        world
+    }
+
+    #[test]
+    fn regions_round_trip() {
+        let path = temp_path("round-trip");
+        let world = small_world();
+        write_region(&path, &world).unwrap();
+        let read = read_region(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(read.chunks == world.chunks);
+        assert_eq!(read.content_hash(), world.content_hash());
+    }
+
+    #[test]
+    fn regions_with_a_different_header_are_rejected() {
+        let path = temp_path("header");
+        write_region(&path, &small_world()).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        // Header layout: magic, version, chunk size, min y, max y
+        let patch = |offset: usize, value: u32| {
+            let mut bytes = bytes.clone();
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            std::fs::write(&path, bytes).unwrap();
+            read_region(&path).err().expect("header was accepted")
+        };
+        let version = patch(4, VERSION + 1);
+        let chunk_size = patch(8, CHUNK_SIZE as u32 * 2);
+        let max_y = patch(16, (WORLD_MAX_Y + 16) as u32);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(version.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(version.to_string(), "unsupported region version");
+        for error in [chunk_size, max_y] {
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(
+                error.to_string(),
+                "region was written with different chunk or world dimensions"
+            );
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::pbr::wireframe::NoWireframe;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::texture::ImageLoaderSettings;
+use bevy::utils::HashSet;
+
+use super::block::BlockRegistry;
+use super::coords::ChunkPos;
+use super::material::{VoxelMaterial, VoxelSettings, VoxelWireframeMaterial};
+use super::mesher::{mesh_chunk, ChunkMeshData};
+use super::textures::texture_array;
+use super::world_generator::{ChunkGenerated, ChunkUnloaded, VoxelWorld};
+
+/// Spawns a mesh entity for every chunk with visible faces produced by
+/// [`WorldGeneratorPlugin`](super::world_generator::WorldGeneratorPlugin), and despawns it
+/// when the chunk is unloaded.
+/// Requires the asset and render plugins, so it is left out of headless apps.
+pub struct WorldRenderPlugin;
+
+impl Plugin for WorldRenderPlugin {
+    fn build(&self, app: &mut App) {
+        // Packed meshes have no position attribute for the prepass and shadow pipelines
+        app.add_plugins((
+            MaterialPlugin::<VoxelMaterial> {
+                prepass_enabled: false,
+                shadows_enabled: false,
+                ..default()
+            },
+            MaterialPlugin::<VoxelWireframeMaterial> {
+                prepass_enabled: false,
+                shadows_enabled: false,
+                ..default()
+            },
+        ))
+        .add_systems(Startup, setup_terrain_material)
+        .init_resource::<BlockTextures>()
+        .add_systems(
+            Update,
+            (
+                load_block_textures.run_if(resource_changed::<BlockRegistry>),
+                build_block_textures,
+                despawn_unloaded_chunks,
+                spawn_chunk_meshes,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct TerrainMaterial(pub Handle<VoxelMaterial>);
+
+/// Source images of the block texture array, in [`BlockRegistry::textures`] order
+#[derive(Resource, Default, PartialEq)]
+pub struct BlockTextures(pub Vec<Handle<Image>>);
+
+/// Marks the mesh entity of the chunk at the given position
+#[derive(Component)]
+pub struct ChunkMesh(pub ChunkPos);
+
+fn setup_terrain_material(mut commands: Commands, mut materials: ResMut<Assets<VoxelMaterial>>) {
+    let material = VoxelMaterial {
+        settings: VoxelSettings { ao_strength: 0.6 },
+        textures: None,
+    };
+    commands.insert_resource(TerrainMaterial(materials.add(material)));
+}
+
+/// Loads the images of the registry's texture layers, again whenever the registry is replaced
+fn load_block_textures(
+    asset_server: Res<AssetServer>,
+    registry: Res<BlockRegistry>,
+    mut textures: ResMut<BlockTextures>,
+) {
+    // The layers are only read to build the array, they never need to reach the GPU
+    let layers = registry
+        .textures()
+        .iter()
+        .map(|texture| {
+            asset_server.load_with_settings(
+                texture.path.clone(),
+                |settings: &mut ImageLoaderSettings| {
+                    settings.asset_usage = RenderAssetUsages::MAIN_WORLD;
+                },
+            )
+        })
+        .collect();
+    textures.set_if_neq(BlockTextures(layers));
+}
+
+/// Rebuilds the texture array once every layer has loaded, and again whenever one is
+/// reloaded or the layers change
+fn build_block_textures(
+    mut events: EventReader<AssetEvent<Image>>,
+    textures: Res<BlockTextures>,
+    terrain: Res<TerrainMaterial>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<VoxelMaterial>>,
+) {
+    let reloaded = events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            textures.0.iter().any(|layer| layer.id() == *id)
+        }
+        _ => false,
+    });
+    if !reloaded && !textures.is_changed() {
+        return;
+    }
+
+    let Some(layers) = textures
+        .0
+        .iter()
+        .map(|layer| images.get(layer))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return;
+    };
+    let Some(array) = texture_array(&layers) else {
+        return;
+    };
+    let array = images.add(array);
+    if let Some(material) = materials.get_mut(&terrain.0) {
+        material.textures = Some(array);
+    }
+}
+
+fn despawn_unloaded_chunks(
+    mut commands: Commands,
+    mut unloaded: EventReader<ChunkUnloaded>,
+    chunk_meshes: Query<(Entity, &ChunkMesh)>,
+) {
+    let unloaded: HashSet<ChunkPos> = unloaded.read().map(|&ChunkUnloaded(pos)| pos).collect();
+    if unloaded.is_empty() {
+        return;
+    }
+    for (entity, &ChunkMesh(pos)) in &chunk_meshes {
+        if unloaded.contains(&pos) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_chunk_meshes(
+    mut commands: Commands,
+    mut generated: EventReader<ChunkGenerated>,
+    mut unloaded: EventReader<ChunkUnloaded>,
+    world: Res<VoxelWorld>,
+    registry: Res<BlockRegistry>,
+    material: Res<TerrainMaterial>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunk_meshes: Query<(Entity, &ChunkMesh, &Handle<Mesh>)>,
+) {
+    // Neighbours of a new chunk are remeshed too, their border faces may now be hidden
+    let mut dirty = HashSet::new();
+    for &ChunkGenerated(pos) in generated.read() {
+        dirty.insert(pos);
+        dirty.extend(pos.neighbours());
+    }
+    // Faces towards an unloaded chunk were culled against it and have to come back
+    for &ChunkUnloaded(pos) in unloaded.read() {
+        dirty.extend(pos.neighbours());
+    }
+    // Block definitions changed, every face may look different
+    if registry.is_changed() {
+        dirty.extend(world.chunks.keys().copied());
+    }
+    if dirty.is_empty() {
+        return;
+    }
+
+    for (entity, &ChunkMesh(pos), handle) in &chunk_meshes {
+        if dirty.remove(&pos) {
+            if let Some(chunk) = world.chunks.get(&pos) {
+                let mesh = mesh_chunk(chunk, &world.neighbours(pos), &registry);
+                if mesh.is_empty() {
+                    commands.entity(entity).despawn();
+                } else {
+                    meshes.insert(handle, mesh.into_mesh());
+                }
+            }
+        }
+    }
+
+    // Chunks of air or buried rock get no entity until a remesh gives them faces
+    for pos in dirty {
+        let Some(chunk) = world.chunks.get(&pos) else {
+            continue;
+        };
+        let mesh = mesh_chunk(chunk, &world.neighbours(pos), &registry);
+        if mesh.is_empty() {
+            continue;
+        }
+
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(mesh.into_mesh()),
+                material: material.0.clone(),
+                transform: Transform::from_translation(pos.origin().as_vec3()),
+                ..default()
+            },
+            ChunkMeshData::bounds(),
+            // Drawn with `VoxelWireframeMaterial` instead
+            NoWireframe,
+            ChunkMesh(pos),
+        ));
+    }
+}
@@ -0,0 +1,271 @@
+use std::io;
+use std::path::Path;
+
+use bevy::asset::LoadedFolder;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::block::{BlockDefinition, BlockFaces, BlockRegistry, RenderLayer};
+use super::chunk::{BlockId, AIR};
+use super::ron_asset::RonAssetLoader;
+
+/// Directory of the block files, relative to the asset folder
+pub const BLOCKS_DIR: &str = "blocks";
+
+/// One block file in [`BLOCKS_DIR`], for example
+///
+/// ```ron
+/// (
+///     id: 3,
+///     name: "grass",
+///     display_name: "Grass",
+///     faces: (top: "grass_top", bottom: "dirt", side: "grass_side"),
+///     map_color: (95, 159, 53),
+/// )
+/// ```
+///
+/// Face textures name images in
+/// [`BLOCK_TEXTURE_DIR`](super::block::BLOCK_TEXTURE_DIR) without the extension.
+#[derive(Asset, TypePath, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockAsset {
+    pub id: BlockId,
+    pub name: String,
+    pub display_name: String,
+    pub faces: FaceTextures,
+    #[serde(default)]
+    pub render_layer: RenderLayer,
+    #[serde(default = "solid_by_default")]
+    pub solid: bool,
+    pub map_color: [u8; 3],
+}
+
+fn solid_by_default() -> bool {
+    true
+}
+
+/// Either one texture for every face or one per side
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FaceTextures {
+    All(String),
+    Faces(BlockFaces),
+}
+
+impl BlockAsset {
+    pub fn definition(&self) -> BlockDefinition {
+        BlockDefinition {
+            name: self.name.clone(),
+            display_name: self.display_name.clone(),
+            render_layer: self.render_layer,
+            solid: self.solid,
+            faces: match &self.faces {
+                FaceTextures::All(texture) => BlockFaces::all(texture.clone()),
+                FaceTextures::Faces(faces) => faces.clone(),
+            },
+            map_color: self.map_color,
+        }
+    }
+}
+
+/// Builds a registry from block files in id order, each paired with the path of its file.
+/// Blocks claiming the air id are skipped with a warning, and so are blocks reusing an id,
+/// keeping the file whose path sorts first.
+pub fn registry_from_blocks<'a>(
+    blocks: impl IntoIterator<Item = (String, &'a BlockAsset)>,
+) -> BlockRegistry {
+    let mut blocks: Vec<(String, &BlockAsset)> = blocks.into_iter().collect();
+    blocks.sort_by(|(a_file, a), (b_file, b)| a.id.cmp(&b.id).then_with(|| a_file.cmp(b_file)));
+
+    let mut registry = BlockRegistry::new();
+    let mut previous: Option<(BlockId, String)> = None;
+    for (file, block) in blocks {
+        if block.id == AIR {
+            warn!(
+                "{file}: id {AIR} is reserved for air, skipping `{}`",
+                block.name
+            );
+            continue;
+        }
+        if let Some((id, first)) = &previous {
+            if *id == block.id {
+                warn!(
+                    "{file}: id {id} is already used by {first}, skipping `{}`",
+                    block.name
+                );
+                continue;
+            }
+        }
+        let definition = block.definition();
+        registry.add_block_textures(&definition.faces);
+        registry.insert(block.id, definition);
+        previous = Some((block.id, file));
+    }
+    registry
+}
+
+/// Reads every block file in `dir` without the asset server, for the headless tools
+pub fn load_blocks(dir: impl AsRef<Path>) -> io::Result<BlockRegistry> {
+    let mut blocks = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "ron") {
+            let text = std::fs::read_to_string(&path)?;
+            let block: BlockAsset = ron::from_str(&text).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {error}", path.display()),
+                )
+            })?;
+            blocks.push((path.display().to_string(), block));
+        }
+    }
+    Ok(registry_from_blocks(
+        blocks.iter().map(|(file, block)| (file.clone(), block)),
+    ))
+}
+
+#[derive(Resource)]
+struct BlockFolder(Handle<LoadedFolder>);
+
+/// Replaces the [`BlockRegistry`] with the block files in [`BLOCKS_DIR`] once they have loaded,
+/// and again whenever one of them changes on disk with the `dev` feature's file watcher.
+/// Chunks are remeshed and the texture array rebuilt by
+/// [`WorldRenderPlugin`](super::render::WorldRenderPlugin) when the registry changes.
+pub struct ResourcePackPlugin;
+
+impl Plugin for ResourcePackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<BlockAsset>()
+            .register_asset_loader(RonAssetLoader::<BlockAsset>::new(&["ron"]))
+            .add_systems(Startup, load_block_folder)
+            .add_systems(Update, apply_block_assets);
+    }
+}
+
+fn load_block_folder(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BlockFolder(asset_server.load_folder(BLOCKS_DIR)));
+}
+
+fn apply_block_assets(
+    mut folder_events: EventReader<AssetEvent<LoadedFolder>>,
+    mut block_events: EventReader<AssetEvent<BlockAsset>>,
+    folder: Res<BlockFolder>,
+    folders: Res<Assets<LoadedFolder>>,
+    blocks: Res<Assets<BlockAsset>>,
+    mut registry: ResMut<BlockRegistry>,
+) {
+    let changed = !folder_events.is_empty() || !block_events.is_empty();
+    folder_events.clear();
+    block_events.clear();
+    if !changed {
+        return;
+    }
+
+    let Some(folder) = folders.get(&folder.0) else {
+        return;
+    };
+    // Wait until every block file has loaded, a partial registry would remesh with holes
+    let Some(assets) = folder
+        .handles
+        .iter()
+        .filter_map(|handle| {
+            let id = handle.id().try_typed::<BlockAsset>().ok()?;
+            let path = handle.path().map_or_else(String::new, ToString::to_string);
+            Some(blocks.get(id).map(|block| (path, block)))
+        })
+        .collect::<Option<Vec<_>>>()
+    else {
+        return;
+    };
+
+    let count = assets.len();
+    *registry = registry_from_blocks(assets);
+    info!("Loaded {count} blocks from {BLOCKS_DIR}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(id: BlockId, name: &str) -> BlockAsset {
+        BlockAsset {
+            id,
+            name: name.to_string(),
+            display_name: name.to_string(),
+            faces: FaceTextures::All(name.to_string()),
+            render_layer: RenderLayer::Opaque,
+            solid: true,
+            map_color: [0, 0, 0],
+        }
+    }
+
+    #[test]
+    fn shipped_blocks_match_the_default_registry() {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let shipped = load_blocks(assets.join(BLOCKS_DIR)).unwrap();
+        let default = BlockRegistry::default();
+        assert_eq!(
+            shipped.iter().collect::<Vec<_>>(),
+            default.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(shipped.textures(), default.textures());
+
+        for texture in shipped.textures() {
+            assert!(
+                assets.join(&texture.path).exists(),
+                "missing texture {}",
+                texture.path
+            );
+        }
+    }
+
+    #[test]
+    fn block_files_fill_in_defaults_and_gaps() {
+        let glass: BlockAsset = ron::from_str(
+            r#"(
+                id: 7,
+                name: "glass",
+                display_name: "Glass",
+                faces: "glass",
+                render_layer: Cutout,
+                map_color: (200, 220, 255),
+            )"#,
+        )
+        .unwrap();
+        assert!(glass.solid);
+
+        let registry = registry_from_blocks([("glass.ron".to_string(), &glass)]);
+        assert_eq!(registry.len(), 8);
+        assert!(!registry.is_opaque(7));
+        assert!(registry.is_solid(7));
+        // Ids between air and the glass are unknown blocks
+        assert!(registry.is_opaque(3));
+        let layer = registry.texture_layer("glass").unwrap();
+        assert_eq!(
+            registry.textures()[layer as usize].path,
+            "textures/blocks/glass.png"
+        );
+        assert_eq!(registry.face_layers(7), BlockFaces::all(layer));
+    }
+
+    #[test]
+    fn air_and_reused_ids_are_skipped() {
+        let (air, stone, rock, dirt) = (
+            block(AIR, "void"),
+            block(1, "stone"),
+            block(1, "rock"),
+            block(2, "dirt"),
+        );
+        let registry = registry_from_blocks([
+            ("b.ron".to_string(), &rock),
+            ("void.ron".to_string(), &air),
+            ("dirt.ron".to_string(), &dirt),
+            ("a.ron".to_string(), &stone),
+        ]);
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.get(AIR).unwrap().name, "air");
+        assert!(!registry.is_solid(AIR));
+        assert_eq!(registry.get(1).unwrap().name, "stone");
+        assert_eq!(registry.get(2).unwrap().name, "dirt");
+    }
+}
@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use super::block::BlockRegistry;
+use super::chunk::{BlockId, ChunkData, AIR, CHUNK_SIZE, DIRT, GRASS, SAND, STONE, WORLD_MAX_Y};
+use super::coords::{BlockPos, ChunkPos, LocalPos};
+use super::world_generator::{generate_chunk, TerrainSampler, WorldSettings};
+
+/// Fills chunks for [`WorldGeneratorPlugin`](super::world_generator::WorldGeneratorPlugin).
+/// Generators run on the async compute pool and must be a pure function of the chunk position.
+pub trait TerrainGenerator: Send + Sync {
+    fn generate(&self, pos: ChunkPos) -> ChunkData;
+}
+
+/// Which [`TerrainGenerator`] the world is built with
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum GeneratorKind {
+    /// Noise terrain shaped by [`WorldSettings::generation`]
+    #[default]
+    Noise,
+    /// Flat layers listed from y 0 up
+    Superflat(Vec<FlatLayer>),
+    /// Every registered block on a grid over a floor checkered per chunk
+    Debug,
+    /// Nothing but air
+    Void,
+}
+
+/// `thickness` blocks of `block` in a superflat world
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlatLayer {
+    pub block: BlockId,
+    pub thickness: usize,
+}
+
+impl GeneratorKind {
+    /// Stone, then dirt, then a grass top
+    pub fn superflat() -> Self {
+        GeneratorKind::Superflat(vec![
+            FlatLayer {
+                block: STONE,
+                thickness: 4,
+            },
+            FlatLayer {
+                block: DIRT,
+                thickness: 3,
+            },
+            FlatLayer {
+                block: GRASS,
+                thickness: 1,
+            },
+        ])
+    }
+
+    /// Parses `noise`, `debug`, `void`, `superflat` for the default layers or a layer list such
+    /// as `superflat=4*stone,3*dirt,grass`, with blocks named as in the registry
+    pub fn parse(text: &str, registry: &BlockRegistry) -> Result<Self, String> {
+        let (name, layers) = match text.split_once('=') {
+            Some((name, layers)) => (name, Some(layers)),
+            None => (text, None),
+        };
+        match (name, layers) {
+            ("noise", None) => Ok(GeneratorKind::Noise),
+            ("debug", None) => Ok(GeneratorKind::Debug),
+            ("void", None) => Ok(GeneratorKind::Void),
+            ("superflat", None) => Ok(GeneratorKind::superflat()),
+            ("superflat", Some(layers)) => layers
+                .split(',')
+                .map(|layer| FlatLayer::parse(layer, registry))
+                .collect::<Result<_, _>>()
+                .map(GeneratorKind::Superflat),
+            _ => Err(format!(
+                "unknown generator `{text}`, expected noise, superflat, debug or void"
+            )),
+        }
+    }
+
+    pub fn build(
+        &self,
+        settings: &WorldSettings,
+        registry: &BlockRegistry,
+    ) -> Arc<dyn TerrainGenerator> {
+        match self {
+            GeneratorKind::Noise => Arc::new(TerrainSampler::new(settings)),
+            GeneratorKind::Superflat(layers) => Arc::new(SuperflatGenerator::new(layers)),
+            GeneratorKind::Debug => Arc::new(DebugGenerator::new(registry)),
+            GeneratorKind::Void => Arc::new(VoidGenerator),
+        }
+    }
+}
+
+impl FlatLayer {
+    /// `stone` or `4*stone`
+    fn parse(text: &str, registry: &BlockRegistry) -> Result<Self, String> {
+        let (thickness, name) = match text.split_once('*') {
+            Some((thickness, name)) => (
+                thickness
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid layer thickness in `{text}`"))?,
+                name,
+            ),
+            None => (1, text),
+        };
+        let name = name.trim();
+        let block = registry
+            .iter()
+            .find(|(_, block)| block.name == name)
+            .map(|(id, _)| id)
+            .ok_or_else(|| format!("unknown block `{name}`"))?;
+        Ok(FlatLayer { block, thickness })
+    }
+}
+
+impl TerrainGenerator for TerrainSampler {
+    fn generate(&self, pos: ChunkPos) -> ChunkData {
+        generate_chunk(self, pos)
+    }
+}
+
+/// The same column of layers everywhere from y 0 up, cut off at the top of the world
+pub struct SuperflatGenerator {
+    column: Vec<BlockId>,
+}
+
+impl SuperflatGenerator {
+    pub fn new(layers: &[FlatLayer]) -> Self {
+        // Thicknesses come from the command line, stop at the top instead of trusting them
+        let column = layers
+            .iter()
+            .flat_map(|layer| std::iter::repeat_n(layer.block, layer.thickness))
+            .take(WORLD_MAX_Y as usize)
+            .collect();
+        Self { column }
+    }
+}
+
+impl TerrainGenerator for SuperflatGenerator {
+    fn generate(&self, pos: ChunkPos) -> ChunkData {
+        let mut chunk = ChunkData::empty();
+        let bottom = pos.origin().0.y;
+        if bottom < 0 {
+            return chunk;
+        }
+        let layers = self.column.iter().skip(bottom as usize).take(CHUNK_SIZE);
+        for (y, &block) in layers.enumerate() {
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    chunk.set(LocalPos::new(x, y, z), block);
+                }
+            }
+        }
+        chunk
+    }
+}
+
+/// Lays out every registered block at y 1, one per grid cell with a gap between them, from the
+/// origin towards positive x and z. The floor alternates between stone and sand per chunk so
+/// chunk borders stand out.
+pub struct DebugGenerator {
+    blocks: Vec<BlockId>,
+    columns: usize,
+}
+
+impl DebugGenerator {
+    /// Spacing between blocks on the grid
+    const SPACING: usize = 2;
+
+    pub fn new(registry: &BlockRegistry) -> Self {
+        let blocks: Vec<BlockId> = registry
+            .iter()
+            .map(|(id, _)| id)
+            .filter(|&id| id != AIR)
+            .collect();
+        let columns = (blocks.len() as f32).sqrt().ceil().max(1.0) as usize;
+        Self { blocks, columns }
+    }
+
+    /// World position of the `index`th block
+    fn cell(&self, index: usize) -> BlockPos {
+        let (column, row) = (index % self.columns, index / self.columns);
+        BlockPos::new(
+            (1 + column * Self::SPACING) as i32,
+            1,
+            (1 + row * Self::SPACING) as i32,
+        )
+    }
+}
+
+impl TerrainGenerator for DebugGenerator {
+    fn generate(&self, pos: ChunkPos) -> ChunkData {
+        let mut chunk = ChunkData::empty();
+        if pos.0.y != 0 {
+            return chunk;
+        }
+        let floor = if (pos.0.x + pos.0.z).rem_euclid(2) == 0 {
+            STONE
+        } else {
+            SAND
+        };
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.set(LocalPos::new(x, 0, z), floor);
+            }
+        }
+
+        for (index, &block) in self.blocks.iter().enumerate() {
+            let cell = self.cell(index);
+            if cell.chunk() == pos {
+                chunk.set(cell.local(), block);
+            }
+        }
+        chunk
+    }
+}
+
+/// An empty world, for testing flight and building from nothing
+pub struct VoidGenerator;
+
+impl TerrainGenerator for VoidGenerator {
+    fn generate(&self, _pos: ChunkPos) -> ChunkData {
+        ChunkData::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn superflat_stacks_layers_from_the_bottom() {
+        let registry = BlockRegistry::default();
+        let kind = GeneratorKind::parse("superflat=2*stone, dirt ,1*grass", &registry).unwrap();
+        let chunk = kind
+            .build(&WorldSettings::default(), &registry)
+            .generate(ChunkPos::new(-3, 0, 7));
+        let column: Vec<BlockId> = (0..4).map(|y| chunk.get(LocalPos::new(5, y, 9))).collect();
+        assert_eq!(column, [STONE, STONE, DIRT, GRASS]);
+        assert_eq!(chunk.surface_height(LocalPos::new(0, 0, 0)), 4);
+        let above = SuperflatGenerator::new(&[FlatLayer {
+            block: STONE,
+            thickness: 40,
+        }])
+        .generate(ChunkPos::new(0, 1, 0));
+        assert_eq!(above.surface_height(LocalPos::new(0, 0, 0)), 8);
+
+        let huge = GeneratorKind::parse("superflat=4000000000*stone", &registry).unwrap();
+        let GeneratorKind::Superflat(layers) = &huge else {
+            panic!("expected a superflat generator")
+        };
+        assert_eq!(
+            SuperflatGenerator::new(layers).column.len(),
+            WORLD_MAX_Y as usize
+        );
+
+        assert!(GeneratorKind::parse("superflat=2*bedrock", &registry).is_err());
+        assert!(GeneratorKind::parse("caves", &registry).is_err());
+    }
+
+    #[test]
+    fn debug_world_shows_every_block_once() {
+        let registry = BlockRegistry::default();
+        let generator = DebugGenerator::new(&registry);
+        let mut placed = Vec::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let chunk = generator.generate(ChunkPos::new(x, 0, z));
+                placed.extend(chunk.blocks().skip(CHUNK_SIZE * CHUNK_SIZE));
+            }
+        }
+        placed.retain(|&block| block != AIR);
+        placed.sort();
+        let expected: Vec<BlockId> = (1..registry.len() as BlockId).collect();
+        assert_eq!(placed, expected);
+    }
+
+    #[test]
+    fn void_is_empty() {
+        let chunk = VoidGenerator.generate(ChunkPos::ZERO);
+        assert_eq!(chunk.content_hash(), ChunkData::empty().content_hash());
+    }
+}
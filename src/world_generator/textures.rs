@@ -0,0 +1,232 @@
+use std::io;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureViewDescriptor, TextureViewDimension,
+};
+use bevy::render::texture::{
+    CompressedImageFormats, ImageAddressMode, ImageFilterMode, ImageSampler,
+    ImageSamplerDescriptor, ImageType,
+};
+
+const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+/// Fills layers whose image cannot be converted to RGBA
+const MISSING_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// Nearest filtering keeps texels sharp up close, blending between mip levels keeps distant
+/// terrain from shimmering. Clamping stops the edge texels from wrapping onto the other side.
+pub fn block_sampler() -> ImageSampler {
+    ImageSampler::Descriptor(ImageSamplerDescriptor {
+        label: Some("block_textures".to_string()),
+        address_mode_u: ImageAddressMode::ClampToEdge,
+        address_mode_v: ImageAddressMode::ClampToEdge,
+        mag_filter: ImageFilterMode::Nearest,
+        min_filter: ImageFilterMode::Nearest,
+        mipmap_filter: ImageFilterMode::Linear,
+        ..default()
+    })
+}
+
+/// Number of mip levels down to 1x1
+pub fn mip_level_count(size: UVec2) -> u32 {
+    u32::BITS - size.max_element().max(1).leading_zeros()
+}
+
+/// Stacks the layers into a 2D texture array with a full mip chain, sampled with
+/// [`block_sampler`]. Every layer is resampled to the size of the first one.
+/// Returns `None` without layers.
+pub fn texture_array(layers: &[&Image]) -> Option<Image> {
+    let size = layers.first()?.size();
+    let mip_level_count = mip_level_count(size);
+
+    // Layer-major order: every mip of the first layer, then every mip of the next
+    let mut data = Vec::new();
+    for layer in layers {
+        data.extend(mip_chain(layer_pixels(layer, size), size));
+    }
+
+    Some(Image {
+        data,
+        texture_descriptor: TextureDescriptor {
+            label: Some("block_textures"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: layers.len() as u32,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: FORMAT,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+        sampler: block_sampler(),
+        texture_view_descriptor: Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..default()
+        }),
+        asset_usage: RenderAssetUsages::RENDER_WORLD,
+    })
+}
+
+/// Lays the layers out side by side in one image, for model formats without texture arrays.
+/// Layer `i` covers `i / n..(i + 1) / n` horizontally, see
+/// [`ExpandedMesh::uvs`](super::mesher::ExpandedMesh::uvs).
+pub fn texture_strip(layers: &[&Image]) -> Option<Image> {
+    let size = layers.first()?.size();
+    let pixels: Vec<Vec<u8>> = layers
+        .iter()
+        .map(|layer| layer_pixels(layer, size))
+        .collect();
+
+    let row = size.x as usize * 4;
+    let mut data = Vec::with_capacity(row * size.y as usize * layers.len());
+    for y in 0..size.y as usize {
+        for layer in &pixels {
+            data.extend_from_slice(&layer[y * row..(y + 1) * row]);
+        }
+    }
+
+    Some(Image::new(
+        Extent3d {
+            width: size.x * layers.len() as u32,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        FORMAT,
+        RenderAssetUsages::MAIN_WORLD,
+    ))
+}
+
+/// Reads an image file outside of the asset server, for the headless tools
+pub fn load_image(path: &Path) -> io::Result<Image> {
+    let bytes = std::fs::read(path)?;
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("png");
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension(extension),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::MAIN_WORLD,
+    )
+    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// RGBA pixels of the first layer of `image`, resampled to `size` with nearest filtering
+fn layer_pixels(image: &Image, size: UVec2) -> Vec<u8> {
+    let pixel_count = (size.x * size.y) as usize;
+    let Some(image) = image.convert(FORMAT) else {
+        return MISSING_COLOR.repeat(pixel_count);
+    };
+    let source = image.size();
+    if source == size {
+        let mut data = image.data;
+        data.truncate(pixel_count * 4);
+        return data;
+    }
+
+    let mut data = Vec::with_capacity(pixel_count * 4);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let (sx, sy) = (x * source.x / size.x, y * source.y / size.y);
+            let i = ((sy * source.x + sx) * 4) as usize;
+            data.extend_from_slice(&image.data[i..i + 4]);
+        }
+    }
+    data
+}
+
+/// The pixels followed by each smaller mip level, every level a 2x2 box filter of the one
+/// above. Colors are averaged in linear space, sRGB bytes averaged directly would darken
+/// every level.
+fn mip_chain(pixels: Vec<u8>, size: UVec2) -> Vec<u8> {
+    let mut level: Vec<[f32; 4]> = pixels
+        .chunks_exact(4)
+        .map(|pixel| {
+            LinearRgba::from(Srgba::rgba_u8(pixel[0], pixel[1], pixel[2], pixel[3])).to_f32_array()
+        })
+        .collect();
+    let mut chain = pixels;
+    let mut size = size;
+    while size.max_element() > 1 {
+        let next = (size / 2).max(UVec2::ONE);
+        let mut mip = Vec::with_capacity((next.x * next.y) as usize);
+        for y in 0..next.y {
+            for x in 0..next.x {
+                let mut sum = [0.0; 4];
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let sx = (x * 2 + dx).min(size.x - 1);
+                    let sy = (y * 2 + dy).min(size.y - 1);
+                    let texel = level[(sy * size.x + sx) as usize];
+                    for (total, value) in sum.iter_mut().zip(texel) {
+                        *total += value;
+                    }
+                }
+                mip.push(sum.map(|total| total / 4.0));
+            }
+        }
+        chain.extend(
+            mip.iter()
+                .flat_map(|&linear| Srgba::from(LinearRgba::from_f32_array(linear)).to_u8_array()),
+        );
+        (level, size) = (mip, next);
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(size: UVec2, color: [u8; 4]) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &color,
+            FORMAT,
+            RenderAssetUsages::MAIN_WORLD,
+        )
+    }
+
+    #[test]
+    fn texture_array_has_a_mip_chain_per_layer() {
+        let red = solid(UVec2::new(16, 16), [255, 0, 0, 255]);
+        // Resampled to the size of the first layer
+        let blue = solid(UVec2::new(16, 32), [0, 0, 255, 255]);
+        let array = texture_array(&[&red, &blue]).unwrap();
+
+        let descriptor = &array.texture_descriptor;
+        assert_eq!(descriptor.mip_level_count, 5);
+        assert_eq!(descriptor.size.depth_or_array_layers, 2);
+        // 16x16 + 8x8 + 4x4 + 2x2 + 1x1 pixels per layer
+        let layer_bytes = 341 * 4;
+        assert_eq!(array.data.len(), layer_bytes * 2);
+        assert_eq!(array.data[layer_bytes - 4..layer_bytes], [255, 0, 0, 255]);
+        assert_eq!(array.data[layer_bytes..layer_bytes + 4], [0, 0, 255, 255]);
+        assert!(texture_array(&[]).is_none());
+    }
+
+    #[test]
+    fn mips_average_the_level_above() {
+        let pixels = [[0, 0, 0, 255], [255, 255, 255, 255]].repeat(2).concat();
+        let chain = mip_chain(pixels, UVec2::new(2, 2));
+        // Half the light of white, not the byte halfway between
+        assert_eq!(chain[16..], [188, 188, 188, 255]);
+        assert_eq!(mip_level_count(UVec2::new(2, 2)), 2);
+        assert_eq!(mip_level_count(UVec2::new(16, 1)), 5);
+    }
+}
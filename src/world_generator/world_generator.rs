@@ -1,221 +1,544 @@
-use bevy::color::palettes::css::{BLUE, RED, WHITE};
-use bevy::pbr::wireframe::WireframeColor;
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use bevy::prelude::*;
-use bevy::render::{
-    mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology,
+use bevy::tasks::{block_on, poll_once, AsyncComputeTaskPool, Task};
+use fastnoise_lite::FastNoiseLite;
+
+use super::block::BlockRegistry;
+use super::chunk::{
+    fnv1a, BlockId, ChunkData, AIR, CHUNK_SIZE, FNV_OFFSET_BASIS, MAX_CHUNK_Y, MIN_CHUNK_Y, STONE,
+    WATER, WORLD_MAX_Y, WORLD_MIN_Y,
 };
-use fastnoise_lite::*;
+use super::config::{BiomeConfig, GenerationConfig};
+use super::coords::{BlockPos, ChunkPos, Face, LocalPos};
+use super::mesher::Neighbours;
+use super::noise_graph::NoiseGraph;
+use super::terrain::{GeneratorKind, TerrainGenerator};
 
+/// Generates voxel data only, so it runs under `MinimalPlugins` without a window or GPU.
+/// Pair it with [`WorldRenderPlugin`](super::render::WorldRenderPlugin) to turn chunks into meshes.
 pub struct WorldGeneratorPlugin;
 
 impl Plugin for WorldGeneratorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, generate_chunk);
+        app.init_resource::<WorldSettings>()
+            .init_resource::<BlockRegistry>()
+            .init_resource::<VoxelWorld>()
+            .init_resource::<PendingChunks>()
+            .add_event::<ChunkGenerated>()
+            .add_event::<ChunkUnloaded>()
+            .add_systems(
+                Update,
+                (
+                    regenerate_chunks,
+                    unload_distant_chunks,
+                    queue_chunk_generation,
+                    collect_generated_chunks,
+                )
+                    .chain(),
+            );
     }
 }
 
-fn generate_chunk(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
-    const CHUNK_SIZE: u32 = 64;
+/// Everything the generator depends on. A chunk is a pure function of these settings and
+/// its position, so the same settings reproduce the same world regardless of the order or
+/// the threads chunks are generated on. Changing them regenerates every loaded chunk.
+#[derive(Resource, Clone)]
+pub struct WorldSettings {
+    pub seed: i32,
+    /// Chunks are loaded up to `radius` chunks away from the [`ChunkLoader`] along x and z, or
+    /// from the origin without one. Chunks further than one more are unloaded.
+    pub radius: i32,
+    /// Chunks loaded above and below the loader, within the world height
+    pub vertical_radius: i32,
+    pub generator: GeneratorKind,
+    /// Terrain of the noise generator
+    pub generation: GenerationConfig,
+}
 
-    let mut noise = FastNoiseLite::with_seed(1337);
+impl Default for WorldSettings {
+    fn default() -> Self {
+        Self {
+            seed: 1337,
+            radius: 2,
+            vertical_radius: 2,
+            generator: GeneratorKind::default(),
+            generation: GenerationConfig::default(),
+        }
+    }
+}
 
-    noise.set_fractal_type(Some(FractalType::FBm));
-    noise.set_fractal_octaves(Some(5));
-    noise.set_frequency(Some(0.035));
-    noise.set_fractal_weighted_strength(Some(0.5));
-    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
+/// Loads the chunks around the entity it is on, usually the camera
+#[derive(Component, Default)]
+pub struct ChunkLoader;
 
-    let mut vertices: Vec<[f32; 3]> = Vec::new();
+/// All loaded chunks, keyed by chunk position
+#[derive(Resource, Default)]
+pub struct VoxelWorld {
+    pub chunks: HashMap<ChunkPos, ChunkData>,
+}
 
-    let mut indices: Vec<u32> = Vec::new();
+impl VoxelWorld {
+    pub fn neighbours(&self, pos: ChunkPos) -> Neighbours<'_> {
+        let get = |face| self.chunks.get(&pos.offset(face));
+        Neighbours {
+            pos_x: get(Face::PosX),
+            neg_x: get(Face::NegX),
+            pos_y: get(Face::PosY),
+            neg_y: get(Face::NegY),
+            pos_z: get(Face::PosZ),
+            neg_z: get(Face::NegZ),
+            world_bottom: pos.0.y <= MIN_CHUNK_Y,
+        }
+    }
 
-    let mut cell = 0;
-    for x in 0..CHUNK_SIZE {
-        for y in 0..CHUNK_SIZE {
-            let noise_normalized =
-                ((noise.get_noise_2d(x as f32, y as f32) + 1.0) / 2.0).round() as u32;
-
-            let height = if noise_normalized == 0 { 4.0 } else { 0.0 };
-
-            // Generate the main cell surface
-            let bl = [x as f32, height, y as f32];
-            let br = [(x + 1) as f32, height, y as f32];
-            let tl = [x as f32, height, (y + 1) as f32];
-            let tr = [(x + 1) as f32, height, (y + 1) as f32];
-
-            vertices.push(bl);
-            vertices.push(br);
-            vertices.push(tl);
-            vertices.push(tr);
-            // First triangle 0 2 1
-            indices.push(cell);
-            indices.push(cell + 2);
-            indices.push(cell + 1);
-            // Second triangle 1 2 3
-            indices.push(cell + 1);
-            indices.push(cell + 2);
-            indices.push(cell + 3);
-
-            cell += 4;
-
-            // Check neighbors to place walls
-
-            // Check north (y + 1)
-            if y + 1 < CHUNK_SIZE {
-                let north_height =
-                    ((noise.get_noise_2d(x as f32, (y + 1) as f32) + 1.0) / 2.0).round() as u32;
-                if north_height != noise_normalized {
-                    // Create a north wall
-                    let bottom_left = [x as f32, height, (y + 1) as f32];
-                    let bottom_right = [(x + 1) as f32, height, (y + 1) as f32];
-                    let top_left = [x as f32, 4.0, (y + 1) as f32];
-                    let top_right = [(x + 1) as f32, 4.0, (y + 1) as f32];
-                    // Push wall vertices and indices
-                    vertices.push(bottom_left);
-                    vertices.push(bottom_right);
-                    vertices.push(top_left);
-                    vertices.push(top_right);
-                    indices.push(cell);
-                    indices.push(cell + 2);
-                    indices.push(cell + 1);
-                    indices.push(cell + 1);
-                    indices.push(cell + 2);
-                    indices.push(cell + 3);
-                    cell += 4;
-                }
+    /// Block at a world position, `None` outside the loaded chunks or the world height
+    pub fn block_at(&self, pos: BlockPos) -> Option<BlockId> {
+        if pos.0.y < WORLD_MIN_Y || pos.0.y >= WORLD_MAX_Y {
+            return None;
+        }
+        Some(self.chunks.get(&pos.chunk())?.get(pos.local()))
+    }
+
+    /// Walks the block grid along a ray and returns the first non-air block within
+    /// `max_distance`, visiting every block the ray passes through (Amanatides and Woo)
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<RaycastHit> {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec3::ZERO {
+            return None;
+        }
+
+        let mut block = BlockPos::containing(origin);
+        let step = IVec3::from_array(direction.to_array().map(|d| {
+            if d > 0.0 {
+                1
+            } else if d < 0.0 {
+                -1
+            } else {
+                0
             }
+        }));
+        let t_delta = direction.abs().recip();
+        let mut t_max = Vec3::from_array(std::array::from_fn(|axis| match step[axis] {
+            1 => (block.0[axis] as f32 + 1.0 - origin[axis]) / direction[axis],
+            -1 => (origin[axis] - block.0[axis] as f32) / -direction[axis],
+            _ => f32::INFINITY,
+        }));
 
-            // Check east (x + 1)
-            if x + 1 < CHUNK_SIZE {
-                let east_height =
-                    ((noise.get_noise_2d((x + 1) as f32, y as f32) + 1.0) / 2.0).round() as u32;
-                if east_height != noise_normalized {
-                    // Create an east wall
-                    let bottom_left = [(x + 1) as f32, height, y as f32];
-                    let bottom_right = [(x + 1) as f32, height, (y + 1) as f32];
-                    let top_left = [(x + 1) as f32, 4.0, y as f32];
-                    let top_right = [(x + 1) as f32, 4.0, (y + 1) as f32];
-                    // Push wall vertices and indices
-                    vertices.push(bottom_left);
-                    vertices.push(bottom_right);
-                    vertices.push(top_left);
-                    vertices.push(top_right);
-                    indices.push(cell);
-                    indices.push(cell + 1);
-                    indices.push(cell + 2);
-                    indices.push(cell + 2);
-                    indices.push(cell + 1);
-                    indices.push(cell + 3);
-                    cell += 4;
-                }
+        let mut face = None;
+        let mut distance = 0.0;
+        while distance <= max_distance {
+            if self.block_at(block).is_some_and(|id| id != AIR) {
+                return Some(RaycastHit {
+                    block,
+                    face,
+                    distance,
+                });
             }
 
-            // Check south (y - 1)
-            if y > 0 {
-                let south_height =
-                    ((noise.get_noise_2d(x as f32, (y - 1) as f32) + 1.0) / 2.0).round() as u32;
-                if south_height != noise_normalized {
-                    // Create a south wall
-                    let bottom_left = [x as f32, height, y as f32];
-                    let bottom_right = [(x + 1) as f32, height, y as f32];
-                    let top_left = [x as f32, 4.0, y as f32];
-                    let top_right = [(x + 1) as f32, 4.0, y as f32];
-                    // Push wall vertices and indices
-                    vertices.push(bottom_left);
-                    vertices.push(bottom_right);
-                    vertices.push(top_left);
-                    vertices.push(top_right);
-                    indices.push(cell);
-                    indices.push(cell + 1);
-                    indices.push(cell + 2);
-                    indices.push(cell + 2);
-                    indices.push(cell + 1);
-                    indices.push(cell + 3);
-                    cell += 4;
-                }
+            let axis = if t_max.x < t_max.y && t_max.x < t_max.z {
+                0
+            } else if t_max.y < t_max.z {
+                1
+            } else {
+                2
+            };
+            distance = t_max[axis];
+            block.0[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+            let mut normal = IVec3::ZERO;
+            normal[axis] = -step[axis];
+            face = Face::from_normal(normal);
+        }
+
+        None
+    }
+
+    /// Combined [`ChunkData::content_hash`] of every chunk, independent of insertion order
+    pub fn content_hash(&self) -> u64 {
+        let mut positions: Vec<&ChunkPos> = self.chunks.keys().collect();
+        positions.sort();
+        positions.into_iter().fold(FNV_OFFSET_BASIS, |hash, pos| {
+            let hash = pos
+                .0
+                .to_array()
+                .iter()
+                .fold(hash, |hash, axis| fnv1a(hash, &axis.to_le_bytes()));
+            fnv1a(hash, &self.chunks[pos].content_hash().to_le_bytes())
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    pub block: BlockPos,
+    /// Face the ray entered through, `None` if the ray started inside the block
+    pub face: Option<Face>,
+    pub distance: f32,
+}
+
+/// Chunks currently being generated on the async compute pool
+#[derive(Resource, Default)]
+pub struct PendingChunks {
+    tasks: HashMap<ChunkPos, Task<ChunkData>>,
+}
+
+impl PendingChunks {
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn contains(&self, pos: ChunkPos) -> bool {
+        self.tasks.contains_key(&pos)
+    }
+
+    /// Positions of the chunks still being generated
+    pub fn positions(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.tasks.keys().copied()
+    }
+}
+
+/// Sent once for every chunk inserted into [`VoxelWorld`]
+#[derive(Event, Clone, Copy)]
+pub struct ChunkGenerated(pub ChunkPos);
+
+/// Sent once for every chunk removed from [`VoxelWorld`] when the loader moves away
+#[derive(Event, Clone, Copy)]
+pub struct ChunkUnloaded(pub ChunkPos);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Mountains,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 3] = [Biome::Plains, Biome::Desert, Biome::Mountains];
+}
+
+/// What the generator decided for a single column of blocks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColumnSample {
+    /// Block y above the terrain, the column is solid from the bottom of the world up to it
+    pub height: i32,
+    pub biome: Biome,
+    /// Blocks of the biome from the [`BiomeTable`](super::config::BiomeTable)
+    pub config: BiomeConfig,
+    /// Water fills the column above the terrain up to this height
+    pub sea_level: i32,
+}
+
+impl ColumnSample {
+    pub fn block_at(&self, y: i32) -> BlockId {
+        if y >= self.height {
+            if y < self.sea_level {
+                WATER
+            } else {
+                AIR
+            }
+        } else if y + 1 == self.height {
+            self.config.surface
+        } else if y + 1 + self.config.subsurface_depth as i32 >= self.height {
+            self.config.subsurface
+        } else {
+            STONE
+        }
+    }
+
+    /// Block y above the top non-air block, the terrain or the water above it
+    pub fn top(&self) -> i32 {
+        self.height.max(self.sea_level.min(WORLD_MAX_Y))
+    }
+
+    /// The block seen from above
+    pub fn surface_block(&self) -> BlockId {
+        self.block_at(self.top() - 1)
+    }
+}
+
+/// Noise sources of the generator, sampled per world column
+pub struct TerrainSampler {
+    height: NoiseGraph,
+    moisture: FastNoiseLite,
+    config: GenerationConfig,
+}
+
+impl TerrainSampler {
+    pub fn new(settings: &WorldSettings) -> Self {
+        let config = &settings.generation;
+        Self {
+            height: NoiseGraph::new(&config.height, settings.seed),
+            moisture: config.moisture.noise(settings.seed),
+            config: config.clone(),
+        }
+    }
+
+    pub fn sample(&self, world_x: i32, world_z: i32) -> ColumnSample {
+        let (x, z) = (world_x as f32, world_z as f32);
+
+        let height_normalized = (self.height.sample(x, z) + 1.0) / 2.0;
+        let height = (self.config.base_height
+            + (height_normalized * self.config.height_variation).round() as i32)
+            .clamp(WORLD_MIN_Y + 1, WORLD_MAX_Y);
+
+        let biomes = &self.config.biomes;
+        let biome = if height_normalized > biomes.mountain_height {
+            Biome::Mountains
+        } else if self.moisture.get_noise_2d(x, z) < biomes.desert_moisture {
+            Biome::Desert
+        } else {
+            Biome::Plains
+        };
+
+        ColumnSample {
+            height,
+            biome,
+            config: *biomes.get(biome),
+            sea_level: self.config.sea_level,
+        }
+    }
+}
+
+pub fn generate_chunk(sampler: &TerrainSampler, pos: ChunkPos) -> ChunkData {
+    let mut chunk = ChunkData::empty();
+    let origin = pos.origin().0;
+    let bottom = origin.y.max(WORLD_MIN_Y);
+
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            let column = sampler.sample(origin.x + x as i32, origin.z + z as i32);
+            let top = column.top().min(origin.y + CHUNK_SIZE as i32);
+            for y in bottom..top {
+                let local = LocalPos::new(x, (y - origin.y) as usize, z);
+                chunk.set(local, column.block_at(y));
             }
+        }
+    }
 
-            // Check west (x - 1)
-            if x > 0 {
-                let west_height =
-                    ((noise.get_noise_2d((x - 1) as f32, y as f32) + 1.0) / 2.0).round() as u32;
-                if west_height != noise_normalized {
-                    // Create a west wall
-                    let bottom_left = [x as f32, height, y as f32];
-                    let bottom_right = [x as f32, height, (y + 1) as f32];
-                    let top_left = [x as f32, 4.0, y as f32];
-                    let top_right = [x as f32, 4.0, (y + 1) as f32];
-                    // Push wall vertices and indices
-                    vertices.push(bottom_left);
-                    vertices.push(bottom_right);
-                    vertices.push(top_left);
-                    vertices.push(top_right);
-                    indices.push(cell);
-                    indices.push(cell + 2);
-                    indices.push(cell + 1);
-                    indices.push(cell + 1);
-                    indices.push(cell + 2);
-                    indices.push(cell + 3);
-                    cell += 4;
+    chunk
+}
+
+/// Updates a headless app until every chunk queued by [`WorldGeneratorPlugin`] is generated
+pub fn run_until_generated(app: &mut App) {
+    loop {
+        app.update();
+        if app.world().resource::<PendingChunks>().is_empty() {
+            break;
+        }
+    }
+}
+
+fn spawn_generation(generator: &Arc<dyn TerrainGenerator>, pos: ChunkPos) -> Task<ChunkData> {
+    let generator = generator.clone();
+    AsyncComputeTaskPool::get().spawn(async move {
+        let mut chunk = generator.generate(pos);
+        chunk.compact();
+        chunk
+    })
+}
+
+/// Replaces every loaded and pending chunk with one generated from the new settings, or from
+/// the new blocks for the debug generator. The old chunks stay in the world until their
+/// replacement arrives, so the terrain never disappears.
+fn regenerate_chunks(
+    settings: Res<WorldSettings>,
+    registry: Res<BlockRegistry>,
+    world: Res<VoxelWorld>,
+    mut pending: ResMut<PendingChunks>,
+) {
+    let blocks_changed = registry.is_changed() && settings.generator == GeneratorKind::Debug;
+    if settings.is_added() || !(settings.is_changed() || blocks_changed) {
+        return;
+    }
+    let positions: Vec<ChunkPos> = world
+        .chunks
+        .keys()
+        .chain(pending.tasks.keys())
+        .copied()
+        .collect();
+    let generator = settings.generator.build(&settings, &registry);
+    // Dropping a task cancels it, so stale pending chunks are never inserted
+    for pos in positions {
+        pending.tasks.insert(pos, spawn_generation(&generator, pos));
+    }
+}
+
+/// Chunk the loader is in, the origin without a loader
+fn load_center(loaders: &Query<&GlobalTransform, With<ChunkLoader>>) -> ChunkPos {
+    loaders.iter().next().map_or(ChunkPos::ZERO, |transform| {
+        ChunkPos::containing(transform.translation())
+    })
+}
+
+/// Whether `pos` is within `margin` chunks of the loading area around `center`
+fn in_load_range(settings: &WorldSettings, center: ChunkPos, pos: ChunkPos, margin: i32) -> bool {
+    let offset = (pos.0 - center.0).abs();
+    offset.x.max(offset.z) <= settings.radius + margin
+        && offset.y <= settings.vertical_radius + margin
+}
+
+/// Drops loaded and pending chunks that fell out of range, with a one chunk margin so moving
+/// back and forth over a chunk border does not reload chunks every frame
+fn unload_distant_chunks(
+    settings: Res<WorldSettings>,
+    loaders: Query<&GlobalTransform, With<ChunkLoader>>,
+    mut world: ResMut<VoxelWorld>,
+    mut pending: ResMut<PendingChunks>,
+    mut unloaded: EventWriter<ChunkUnloaded>,
+) {
+    let center = load_center(&loaders);
+    pending
+        .tasks
+        .retain(|&pos, _| in_load_range(&settings, center, pos, 1));
+
+    let mut distant: Vec<ChunkPos> = world
+        .chunks
+        .keys()
+        .copied()
+        .filter(|&pos| !in_load_range(&settings, center, pos, 1))
+        .collect();
+    distant.sort();
+    for pos in distant {
+        world.chunks.remove(&pos);
+        unloaded.send(ChunkUnloaded(pos));
+    }
+}
+
+fn queue_chunk_generation(
+    settings: Res<WorldSettings>,
+    registry: Res<BlockRegistry>,
+    loaders: Query<&GlobalTransform, With<ChunkLoader>>,
+    world: Res<VoxelWorld>,
+    mut pending: ResMut<PendingChunks>,
+) {
+    let center = load_center(&loaders);
+    let (radius, vertical_radius) = (settings.radius, settings.vertical_radius);
+    let center = center.0;
+    let min_y = (center.y - vertical_radius).max(MIN_CHUNK_Y);
+    let max_y = (center.y + vertical_radius).min(MAX_CHUNK_Y);
+
+    let mut generator = None;
+    for x in -radius..=radius {
+        for z in -radius..=radius {
+            for y in min_y..=max_y {
+                let pos = ChunkPos::new(center.x + x, y, center.z + z);
+                if world.chunks.contains_key(&pos) || pending.tasks.contains_key(&pos) {
+                    continue;
                 }
+                let generator =
+                    generator.get_or_insert_with(|| settings.generator.build(&settings, &registry));
+                pending.tasks.insert(pos, spawn_generation(generator, pos));
             }
         }
     }
+}
+
+fn collect_generated_chunks(
+    mut pending: ResMut<PendingChunks>,
+    mut world: ResMut<VoxelWorld>,
+    mut generated: EventWriter<ChunkGenerated>,
+) {
+    let mut finished = Vec::new();
+    pending
+        .tasks
+        .retain(|&pos, task| match block_on(poll_once(task)) {
+            Some(chunk) => {
+                finished.push((pos, chunk));
+                false
+            }
+            None => true,
+        });
+
+    // Tasks finish in any order, sorting keeps the event order reproducible
+    finished.sort_by_key(|&(pos, _)| pos);
+    for (pos, chunk) in finished {
+        world.chunks.insert(pos, chunk);
+        generated.send(ChunkGenerated(pos));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world_with_block(pos: BlockPos) -> VoxelWorld {
+        let mut chunk = ChunkData::empty();
+        chunk.set(pos.local(), STONE);
+        let mut world = VoxelWorld::default();
+        world.chunks.insert(pos.chunk(), chunk);
+        world
+            .chunks
+            .entry(ChunkPos::ZERO)
+            .or_insert_with(ChunkData::empty);
+        world
+    }
+
+    #[test]
+    fn block_at_uses_floor_division_for_negative_positions() {
+        let world = world_with_block(BlockPos::new(-1, -5, -33));
+        assert_eq!(world.block_at(BlockPos::new(-1, -5, -33)), Some(STONE));
+        assert_eq!(world.block_at(BlockPos::new(0, 5, 0)), Some(AIR));
+        assert_eq!(world.block_at(BlockPos::new(0, -1, 0)), None);
+        assert_eq!(world.block_at(BlockPos::new(100, 5, 100)), None);
+        assert_eq!(world.block_at(BlockPos::new(0, WORLD_MIN_Y - 1, 0)), None);
+    }
+
+    #[test]
+    fn raycast_hits_the_first_block_and_reports_the_entered_face() {
+        let world = world_with_block(BlockPos::new(-3, 10, 4));
+        let hit = world
+            .raycast(Vec3::new(4.5, 10.5, 4.5), Vec3::NEG_X, 20.0)
+            .unwrap();
+        assert_eq!(hit.block, BlockPos::new(-3, 10, 4));
+        assert_eq!(hit.face, Some(Face::PosX));
+        assert!((hit.distance - 6.5).abs() < 1e-5);
+
+        assert!(world
+            .raycast(Vec3::new(4.5, 10.5, 4.5), Vec3::NEG_X, 5.0)
+            .is_none());
+        assert!(world
+            .raycast(Vec3::new(4.5, 10.5, 4.5), Vec3::X, 20.0)
+            .is_none());
+    }
 
-    let mesh = Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
-    )
-    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vertices)
-    .with_inserted_indices(Indices::U32(indices));
-
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(mesh), // Add the custom cube mesh
-            material: materials.add(Color::from(WHITE)),
-            ..default()
-        },
-        WireframeColor {
-            color: Color::BLACK,
-        },
-    ));
-}
-
-fn debug_cube() -> Mesh {
-    let vertices = vec![
-        [-0.5, -0.5, -0.5], // 0: left  bottom back
-        [0.5, -0.5, -0.5],  // 1: right bottom back
-        [0.5, 0.5, -0.5],   // 2: right top    back
-        [-0.5, 0.5, -0.5],  // 3: left  top    back
-        [-0.5, -0.5, 0.5],  // 4: left  bottom front
-        [0.5, -0.5, 0.5],   // 5: right bottom front
-        [0.5, 0.5, 0.5],    // 6: right top    front
-        [-0.5, 0.5, 0.5],   // 7: left  top    front
-    ];
-
-    let indices = vec![
-        // Front face
-        4, 5, 6, 4, 6, 7, // Back face
-        1, 0, 3, 1, 3, 2, // Left face
-        0, 4, 7, 0, 7, 3, // Right face
-        5, 1, 2, 5, 2, 6, // Top face
-        3, 7, 6, 3, 6, 2, // Bottom face
-        4, 0, 1, 4, 1, 5,
-    ];
-
-    let mut mesh = Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
-    )
-    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vertices)
-    .with_inserted_indices(Indices::U32(indices));
-
-    mesh.duplicate_vertices();
-    mesh.compute_flat_normals();
-
-    return mesh;
+    #[test]
+    fn chunks_follow_the_loader_within_the_world_height() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, WorldGeneratorPlugin))
+            .insert_resource(WorldSettings {
+                radius: 1,
+                vertical_radius: 1,
+                generator: GeneratorKind::Void,
+                ..default()
+            });
+        let loader = app
+            .world_mut()
+            .spawn((ChunkLoader, GlobalTransform::default()))
+            .id();
+        run_until_generated(&mut app);
+        assert_eq!(app.world().resource::<VoxelWorld>().chunks.len(), 27);
+
+        // Chunks one past the radius are kept, the ones behind them are unloaded
+        let size = CHUNK_SIZE as f32;
+        *app.world_mut().get_mut::<GlobalTransform>(loader).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(3.5 * size, 0.0, 0.0));
+        run_until_generated(&mut app);
+        let world = app.world().resource::<VoxelWorld>();
+        assert_eq!(world.chunks.len(), 36);
+        assert!(world.chunks.keys().all(|pos| (1..=4).contains(&pos.0.x)));
+
+        // Nothing is loaded below the bottom of the world
+        *app.world_mut().get_mut::<GlobalTransform>(loader).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(0.0, WORLD_MIN_Y as f32, 0.0));
+        run_until_generated(&mut app);
+        let world = app.world().resource::<VoxelWorld>();
+        assert!(world.chunks.keys().all(|pos| pos.0.y >= MIN_CHUNK_Y));
+        assert!(world.chunks.contains_key(&ChunkPos::new(0, MIN_CHUNK_Y, 0)));
+    }
 }
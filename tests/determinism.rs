@@ -0,0 +1,183 @@
+//! Golden-hash tests for world generation.
+//!
+//! `tests/golden/chunk_hashes.txt` holds the expected content hash of a set of chunks for a set
+//! of seeds. Any change to the generator that alters the world will fail here; if the change
+//! is intended, regenerate the file with `UPDATE_GOLDEN=1 cargo test --test determinism`.
+
+use std::fmt::Write;
+use std::path::Path;
+use std::thread;
+
+use bevy::prelude::*;
+
+use bevy_minecraft_clone::world_generator::coords::ChunkPos;
+use bevy_minecraft_clone::world_generator::world_generator::{
+    generate_chunk, run_until_generated, TerrainSampler, VoxelWorld, WorldGeneratorPlugin,
+    WorldSettings,
+};
+
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/chunk_hashes.txt");
+
+const SEEDS: [i32; 5] = [0, 1, 1337, -42, 987_654_321];
+const POSITIONS: [ChunkPos; 5] = [
+    ChunkPos::new(0, 0, 0),
+    ChunkPos::new(1, 0, 0),
+    ChunkPos::new(-1, -1, -1),
+    ChunkPos::new(17, 0, -23),
+    ChunkPos::new(-300, 0, 512),
+];
+
+fn settings(seed: i32) -> WorldSettings {
+    WorldSettings { seed, ..default() }
+}
+
+fn chunk_hash(settings: &WorldSettings, pos: ChunkPos) -> u64 {
+    generate_chunk(&TerrainSampler::new(settings), pos).content_hash()
+}
+
+fn golden_table() -> String {
+    let mut table = String::from("# seed chunk_x chunk_y chunk_z content_hash\n");
+    for seed in SEEDS {
+        for pos in POSITIONS {
+            let hash = chunk_hash(&settings(seed), pos);
+            writeln!(table, "{seed} {pos} {hash:016x}").unwrap();
+        }
+    }
+    table
+}
+
+#[test]
+fn chunk_hashes_match_golden_values() {
+    let actual = golden_table();
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(GOLDEN_PATH, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(Path::new(GOLDEN_PATH))
+        .expect("missing golden file, run with UPDATE_GOLDEN=1 to create it");
+    for (expected, actual) in expected.lines().zip(actual.lines()) {
+        assert_eq!(
+            expected, actual,
+            "generated world differs from golden value"
+        );
+    }
+    assert_eq!(expected.lines().count(), actual.lines().count());
+}
+
+#[test]
+fn generation_order_and_threads_do_not_change_chunks() {
+    let settings = settings(1337);
+    let mut positions: Vec<ChunkPos> = (-3..=3)
+        .flat_map(|x| (-1..=1).flat_map(move |y| (-3..=3).map(move |z| ChunkPos::new(x, y, z))))
+        .collect();
+
+    let sequential: Vec<u64> = positions
+        .iter()
+        .map(|&pos| chunk_hash(&settings, pos))
+        .collect();
+
+    // Generate the same chunks in reverse on several threads at once
+    positions.reverse();
+    let parallel: Vec<(ChunkPos, u64)> = thread::scope(|scope| {
+        positions
+            .chunks(5)
+            .map(|batch| {
+                let settings = &settings;
+                scope.spawn(move || {
+                    batch
+                        .iter()
+                        .map(|&pos| (pos, chunk_hash(settings, pos)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    positions.reverse();
+    for (pos, hash) in parallel {
+        let index = positions.iter().position(|&p| p == pos).unwrap();
+        assert_eq!(sequential[index], hash, "chunk {pos} differs");
+    }
+}
+
+#[test]
+fn plugin_generates_the_same_world_as_direct_generation() {
+    let settings = WorldSettings {
+        radius: 2,
+        vertical_radius: 2,
+        ..settings(42)
+    };
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, WorldGeneratorPlugin))
+        .insert_resource(settings.clone());
+    run_until_generated(&mut app);
+
+    let mut expected = VoxelWorld::default();
+    let sampler = TerrainSampler::new(&settings);
+    for x in -settings.radius..=settings.radius {
+        for y in -settings.vertical_radius..=settings.vertical_radius {
+            for z in -settings.radius..=settings.radius {
+                let pos = ChunkPos::new(x, y, z);
+                expected.chunks.insert(pos, generate_chunk(&sampler, pos));
+            }
+        }
+    }
+
+    let world = app.world().resource::<VoxelWorld>();
+    assert_eq!(world.chunks.len(), 125);
+    assert_eq!(world.content_hash(), expected.content_hash());
+}
+
+#[test]
+fn changing_settings_regenerates_loaded_chunks() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, WorldGeneratorPlugin))
+        .insert_resource(WorldSettings {
+            radius: 1,
+            vertical_radius: 1,
+            ..settings(42)
+        });
+    run_until_generated(&mut app);
+    let before = app.world().resource::<VoxelWorld>().content_hash();
+
+    let mut settings = app.world().resource::<WorldSettings>().clone();
+    settings.generation.sea_level = 20;
+    settings.generation.base_height = 4;
+    app.insert_resource(settings.clone());
+    run_until_generated(&mut app);
+
+    let mut expected = VoxelWorld::default();
+    let sampler = TerrainSampler::new(&settings);
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                let pos = ChunkPos::new(x, y, z);
+                expected.chunks.insert(pos, generate_chunk(&sampler, pos));
+            }
+        }
+    }
+
+    let world = app.world().resource::<VoxelWorld>();
+    assert_ne!(world.content_hash(), before);
+    assert_eq!(world.chunks.len(), 27);
+    assert_eq!(world.content_hash(), expected.content_hash());
+}
+
+#[test]
+fn different_seeds_produce_different_worlds() {
+    let hashes: Vec<u64> = SEEDS
+        .iter()
+        .map(|&seed| chunk_hash(&settings(seed), ChunkPos::ZERO))
+        .collect();
+    for (i, a) in hashes.iter().enumerate() {
+        for b in &hashes[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}